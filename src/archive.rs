@@ -0,0 +1,570 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::byte_reader::Snapshot;
+use crate::decompress::{limit_decompressed, Compression};
+
+const TAR_BLOCK: u64 = 512;
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER: u64 = 60;
+const ZIP_END_OF_DIRECTORY: &[u8] = b"PK\x05\x06";
+const ZIP64_END_LOCATOR: &[u8] = b"PK\x06\x07";
+const ZIP_DIRECTORY_ENTRY: &[u8] = b"PK\x01\x02";
+const ZIP_LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+/// The end of directory record is at the end of the file, but may be followed by a comment of up to 64 KiB.
+const ZIP_END_SEARCH: u64 = 22 + 0xFFFF;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    Ar,
+}
+
+impl ArchiveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArchiveKind::Zip => "zip",
+            ArchiveKind::Tar => "tar",
+            ArchiveKind::Ar => "ar",
+        }
+    }
+}
+
+/// How the data of a member is stored in the archive.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Method {
+    Stored,
+    Deflated,
+    Compressed(Compression),
+    Encrypted,
+    Unsupported(u16),
+}
+
+impl Method {
+    fn of_zip(method: u16, flags: u16) -> Method {
+        if flags & 1 != 0 {
+            return Method::Encrypted;
+        }
+        match method {
+            0 => Method::Stored,
+            8 => Method::Deflated,
+            12 => Method::Compressed(Compression::Bzip2),
+            93 => Method::Compressed(Compression::Zstd),
+            95 => Method::Compressed(Compression::Xz),
+            _ => Method::Unsupported(method),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Method::Stored => "stored".to_string(),
+            Method::Deflated => "deflate".to_string(),
+            Method::Compressed(compression) => compression.name().to_string(),
+            Method::Encrypted => "encrypted".to_string(),
+            Method::Unsupported(method) => format!("method {}", method),
+        }
+    }
+}
+
+/// A file in an archive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    /// The size of the member when it is extracted.
+    pub size: u64,
+    /// Where the data of the member starts in the archive.
+    pub offset: u64,
+    /// The length of the data in the archive, which differs from the size if the data is compressed.
+    pub stored_size: u64,
+    pub method: Method,
+}
+
+impl Member {
+    /// Extract the member from the archive, as it is read.
+    pub fn open(&self, archive: Snapshot) -> Result<Box<dyn Read + Send>> {
+        let end = self.offset.checked_add(self.stored_size)
+            .ok_or_else(|| broken(format!("The data of {} does not fit in the archive.", self.name)))?;
+        let data = SnapshotRange { snapshot: archive, offset: self.offset, end };
+        match self.method {
+            Method::Stored => Ok(Box::new(data)),
+            Method::Deflated => Ok(limit_decompressed(flate2::read::DeflateDecoder::new(data))),
+            Method::Compressed(compression) => compression.decoder(data),
+            Method::Encrypted | Method::Unsupported(_) => {
+                let message = format!("{} cannot be extracted, because it is {}.", self.name, self.method.name());
+                Err(Error::new(ErrorKind::InvalidData, message))
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Archive {
+    pub kind: ArchiveKind,
+    pub members: Vec<Member>,
+}
+
+impl Archive {
+    /// List the members of the archive, if the source is a zip, tar or ar archive.
+    /// Only the headers are read, so this is quick even for large archives.
+    pub fn read(source: &mut Snapshot) -> Result<Option<Archive>> {
+        let head = read_up_to(source, 0, TAR_BLOCK)?;
+        let (kind, members) = if head.starts_with(AR_MAGIC) {
+            (ArchiveKind::Ar, read_ar(source)?)
+        } else if is_tar_header(&head) {
+            (ArchiveKind::Tar, read_tar(source)?)
+        } else if let Some(members) = read_zip(source)? {
+            (ArchiveKind::Zip, members)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Archive { kind, members }))
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+/// Reads a range of bytes from a snapshot.
+struct SnapshotRange {
+    snapshot: Snapshot,
+    offset: u64,
+    end: u64,
+}
+
+impl Read for SnapshotRange {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let left = usize::try_from(self.end - self.offset).unwrap_or(usize::MAX);
+        let len = buf.len().min(left);
+        let bytes_read = self.snapshot.read_at(self.offset, &mut buf[..len])?;
+        self.offset += u64::try_from(bytes_read).unwrap();
+        Ok(bytes_read)
+    }
+}
+
+/// Read the bytes at the offset, or fewer if the source ends before them.
+fn read_up_to(source: &mut Snapshot, offset: u64, length: u64) -> Result<Vec<u8>> {
+    // The length comes from a header, so it is not trusted to be small enough to allocate.
+    let length = length.min(source.len().saturating_sub(offset));
+    let mut buf = vec![0; usize::try_from(length).unwrap()];
+    let bytes_read = source.read_at(offset, &mut buf)?;
+    buf.truncate(bytes_read);
+    Ok(buf)
+}
+
+fn read_exactly(source: &mut Snapshot, offset: u64, length: u64) -> Result<Vec<u8>> {
+    let buf = read_up_to(source, offset, length)?;
+    if u64::try_from(buf.len()).unwrap() < length {
+        let message = format!("The archive ends in the middle of a header at 0x{:X}.", offset);
+        return Err(Error::new(ErrorKind::UnexpectedEof, message));
+    }
+    Ok(buf)
+}
+
+/// The end of data of the given size at the offset, if the data fits in the archive.
+fn data_end(source: &Snapshot, offset: u64, size: u64) -> Result<u64> {
+    offset.checked_add(size)
+        .filter(|&end| end <= source.len())
+        .ok_or_else(|| broken(format!("The data at 0x{:X} is {} bytes long, which does not fit in the archive.", offset, size)))
+}
+
+fn broken(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// The text up to the first NUL byte.
+fn field_text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(buf)
+}
+
+fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() < 512 || block[..512].iter().all(|&b| b == 0) {
+        return false;
+    }
+    // The checksum is the sum of the header bytes, with the checksum field itself counted as spaces.
+    let sum: u64 = block[..512].iter().enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { u64::from(b' ') } else { u64::from(b) })
+        .sum();
+    tar_number(&block[148..156]) == Some(sum)
+}
+
+/// Tar numbers are octal text, or big-endian binary if the high bit of the first byte is set.
+fn tar_number(field: &[u8]) -> Option<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Some(field[1..].iter().fold(0, |n, &b| (n << 8) | u64::from(b)));
+    }
+    let text = field_text(field);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// The value of the `path` record in a PAX extended header.
+fn pax_path(records: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(records);
+    text.lines()
+        .filter_map(|record| record.split_once(' ').map(|(_, record)| record))
+        .find_map(|record| record.strip_prefix("path=").map(|path| path.to_string()))
+}
+
+fn read_tar(source: &mut Snapshot) -> Result<Vec<Member>> {
+    let mut members = Vec::new();
+    let mut offset = 0;
+    let mut long_name = None;
+    while offset + TAR_BLOCK <= source.len() {
+        let header = read_exactly(source, offset, TAR_BLOCK)?;
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if !is_tar_header(&header) {
+            return Err(broken(format!("Broken tar header at 0x{:X}.", offset)));
+        }
+        let size = tar_number(&header[124..136])
+            .ok_or_else(|| broken(format!("Broken size in the tar header at 0x{:X}.", offset)))?;
+        let data_offset = offset + TAR_BLOCK;
+        let end = data_end(source, data_offset, size)?;
+        match header[156] {
+            // The name of the next member is too long for its header, so it is given here instead.
+            b'L' => long_name = Some(field_text(&read_exactly(source, data_offset, size)?)),
+            b'x' => long_name = pax_path(&read_exactly(source, data_offset, size)?).or(long_name),
+            b'0' | b'\0' | b'7' => {
+                let mut name = field_text(&header[..100]);
+                let prefix = field_text(&header[345..500]);
+                if header[257..263] == *b"ustar\0" && !prefix.is_empty() {
+                    name = format!("{}/{}", prefix, name);
+                }
+                members.push(Member {
+                    name: long_name.take().unwrap_or(name),
+                    size,
+                    offset: data_offset,
+                    stored_size: size,
+                    method: Method::Stored,
+                });
+            },
+            _ => long_name = None,
+        }
+        // The data is padded to whole blocks. The end is at most the length of the archive, so this does not overflow.
+        offset = end.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+    }
+    Ok(members)
+}
+
+fn read_ar(source: &mut Snapshot) -> Result<Vec<Member>> {
+    let mut members = Vec::new();
+    let mut long_names = Vec::new();
+    let mut offset = u64::try_from(AR_MAGIC.len()).unwrap();
+    while offset + AR_HEADER <= source.len() {
+        let header = read_exactly(source, offset, AR_HEADER)?;
+        if header[58..60] != *b"`\n" {
+            return Err(broken(format!("Broken ar header at 0x{:X}.", offset)));
+        }
+        let size = String::from_utf8_lossy(&header[48..58]).trim().parse::<u64>()
+            .map_err(|_| broken(format!("Broken size in the ar header at 0x{:X}.", offset)))?;
+        let name = String::from_utf8_lossy(&header[..16]).trim_end().to_string();
+        let mut data_offset = offset + AR_HEADER;
+        let end = data_end(source, data_offset, size)?;
+        let mut data_size = size;
+        let name = if name == "/" || name == "/SYM64/" || name == "__.SYMDEF" || name == "__.SYMDEF SORTED" {
+            // The symbol table of a library.
+            None
+        } else if name == "//" {
+            // The table of long names, used by GNU ar.
+            long_names = read_exactly(source, data_offset, size)?;
+            None
+        } else if let Some(index) = name.strip_prefix('/').and_then(|index| index.parse::<usize>().ok()) {
+            let names = long_names.get(index..).unwrap_or_default();
+            let end = names.iter().position(|&b| b == b'\n').unwrap_or(names.len());
+            Some(String::from_utf8_lossy(&names[..end]).trim_end_matches('/').to_string())
+        } else if let Some(length) = name.strip_prefix("#1/").and_then(|length| length.parse::<u64>().ok()) {
+            // BSD ar puts long names in front of the data.
+            let long_name = read_exactly(source, data_offset, length.min(size))?;
+            data_offset += length.min(size);
+            data_size -= length.min(size);
+            Some(field_text(&long_name))
+        } else {
+            Some(name.trim_end_matches('/').to_string())
+        };
+        if let Some(name) = name {
+            members.push(Member { name, size: data_size, offset: data_offset, stored_size: data_size, method: Method::Stored });
+        }
+        // The data is padded to an even length.
+        offset = end + end % 2;
+    }
+    Ok(members)
+}
+
+fn read_zip(source: &mut Snapshot) -> Result<Option<Vec<Member>>> {
+    let search_start = source.len().saturating_sub(ZIP_END_SEARCH);
+    let tail = read_up_to(source, search_start, source.len() - search_start)?;
+    let end_index = match tail.windows(4).rposition(|window| window == ZIP_END_OF_DIRECTORY) {
+        Some(index) if index + 22 <= tail.len() => index,
+        _ => return Ok(None),
+    };
+    match read_zip_directory(source, &tail, end_index) {
+        Ok(members) => Ok(Some(members)),
+        // The signature of the end of directory record may just happen to be at the end of a file that is not a zip archive.
+        Err(error) if error.kind() == ErrorKind::InvalidData || error.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Read the central directory that the end of directory record at `end_index` in the tail of the archive points to.
+fn read_zip_directory(source: &mut Snapshot, tail: &[u8], end_index: usize) -> Result<Vec<Member>> {
+    let end = &tail[end_index..];
+    let mut entries = u64::from(le_u16(end, 10));
+    let mut directory_offset = u64::from(le_u32(end, 16));
+    if end_index >= 20 && &tail[end_index - 20..end_index - 16] == ZIP64_END_LOCATOR {
+        // The archive is too large for the sizes and offsets of the classic end of directory record.
+        let zip64_end = read_exactly(source, le_u64(tail, end_index - 12), 56)?;
+        entries = le_u64(&zip64_end, 32);
+        directory_offset = le_u64(&zip64_end, 48);
+    }
+
+    let mut members = Vec::new();
+    let mut offset = directory_offset;
+    for _ in 0..entries {
+        let entry = read_exactly(source, offset, 46)?;
+        if &entry[..4] != ZIP_DIRECTORY_ENTRY {
+            return Err(broken(format!("Broken zip directory entry at 0x{:X}.", offset)));
+        }
+        let name_length = u64::from(le_u16(&entry, 28));
+        let extra_length = u64::from(le_u16(&entry, 30));
+        let comment_length = u64::from(le_u16(&entry, 32));
+        let name = read_exactly(source, offset + 46, name_length)?;
+        let extra = read_exactly(source, offset + 46 + name_length, extra_length)?;
+        offset += 46 + name_length + extra_length + comment_length;
+
+        let name = String::from_utf8_lossy(&name).into_owned();
+        if name.ends_with('/') {
+            // A directory.
+            continue;
+        }
+        let mut size = u64::from(le_u32(&entry, 24));
+        let mut stored_size = u64::from(le_u32(&entry, 20));
+        let mut header_offset = u64::from(le_u32(&entry, 42));
+        let mut zip64 = zip64_fields(&extra).into_iter();
+        for field in [&mut size, &mut stored_size, &mut header_offset].iter_mut() {
+            if **field == u64::from(u32::MAX) {
+                **field = zip64.next().ok_or_else(|| broken(format!("Missing zip64 sizes for {}.", name)))?;
+            }
+        }
+
+        let local_header = read_exactly(source, header_offset, 30)?;
+        if &local_header[..4] != ZIP_LOCAL_HEADER {
+            return Err(broken(format!("Broken zip header for {} at 0x{:X}.", name, header_offset)));
+        }
+        let data_offset = header_offset + 30 + u64::from(le_u16(&local_header, 26)) + u64::from(le_u16(&local_header, 28));
+        data_end(source, data_offset, stored_size)?;
+        let method = Method::of_zip(le_u16(&entry, 10), le_u16(&entry, 8));
+        members.push(Member { name, size, offset: data_offset, stored_size, method });
+    }
+    Ok(members)
+}
+
+/// The values of the zip64 extended information field, which replace the sizes and offset that do not fit in 32 bits.
+fn zip64_fields(extra: &[u8]) -> Vec<u64> {
+    let mut at = 0;
+    while at + 4 <= extra.len() {
+        let id = le_u16(extra, at);
+        let length = usize::from(le_u16(extra, at + 2));
+        let data = &extra[(at + 4).min(extra.len())..(at + 4 + length).min(extra.len())];
+        if id == 1 {
+            return data.chunks_exact(8).map(|chunk| le_u64(chunk, 0)).collect();
+        }
+        at += 4 + length;
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::*;
+
+    fn snapshot_of(bytes: &[u8]) -> Snapshot {
+        let mut tmpf = tempfile::tempfile().unwrap();
+        tmpf.write_all(bytes).unwrap();
+        Snapshot::new(tmpf).unwrap()
+    }
+
+    fn extract(bytes: &[u8], member: &Member) -> Vec<u8> {
+        let mut contents = Vec::new();
+        member.open(snapshot_of(bytes)).unwrap().read_to_end(&mut contents).unwrap();
+        contents
+    }
+
+    fn tar_header(name: &str, size: usize, kind: u8) -> Vec<u8> {
+        let mut header = vec![0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        header
+    }
+
+    fn tar_entry(tar: &mut Vec<u8>, name: &str, data: &[u8], kind: u8) {
+        tar.extend(tar_header(name, data.len(), kind));
+        tar.extend(data);
+        tar.resize(tar.len().div_ceil(512) * 512, 0);
+    }
+
+    #[test]
+    fn listing_tar_members() {
+        let long_name = format!("{}/file.txt", "d".repeat(120));
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "hello.txt", b"Hello, tar!", b'0');
+        tar_entry(&mut tar, "dir/", b"", b'5');
+        tar_entry(&mut tar, "././@LongLink", long_name.as_bytes(), b'L');
+        tar_entry(&mut tar, "truncated", b"long", b'0');
+        tar.extend(vec![0; 1024]);
+
+        let archive = Archive::read(&mut snapshot_of(&tar)).unwrap().unwrap();
+        assert_eq!(archive.kind, ArchiveKind::Tar);
+        assert_eq!(archive.members, vec![
+            Member { name: "hello.txt".to_string(), size: 11, offset: 512, stored_size: 11, method: Method::Stored },
+            Member { name: long_name.clone(), size: 4, offset: 512 * 6, stored_size: 4, method: Method::Stored },
+        ]);
+        assert_eq!(extract(&tar, archive.member(&long_name).unwrap()), b"long");
+    }
+
+    #[test]
+    fn listing_ar_members() {
+        let mut ar = AR_MAGIC.to_vec();
+        let mut entry = |name: &str, data: &[u8]| {
+            ar.extend(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", name, 0, 0, 0, 644, data.len()).as_bytes());
+            ar.extend(data);
+            if data.len() % 2 == 1 {
+                ar.push(b'\n');
+            }
+        };
+        entry("/", b"\0\0\0\0");
+        entry("//", b"a_rather_long_object_name.o/\n");
+        entry("short.o/", b"odd");
+        entry("/0", b"long");
+        entry("#1/8", b"bsd.o\0\0\0data");
+
+        let archive = Archive::read(&mut snapshot_of(&ar)).unwrap().unwrap();
+        assert_eq!(archive.kind, ArchiveKind::Ar);
+        let names: Vec<&str> = archive.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["short.o", "a_rather_long_object_name.o", "bsd.o"]);
+        assert_eq!(extract(&ar, &archive.members[0]), b"odd");
+        assert_eq!(extract(&ar, &archive.members[1]), b"long");
+        assert_eq!(extract(&ar, &archive.members[2]), b"data");
+    }
+
+    fn zip_entry(zip: &mut Vec<u8>, directory: &mut Vec<u8>, name: &str, method: u16, data: &[u8], size: usize) {
+        let header_offset = u32::try_from(zip.len()).unwrap();
+        let sizes = [&u32::try_from(data.len()).unwrap().to_le_bytes()[..], &u32::try_from(size).unwrap().to_le_bytes()[..]].concat();
+        zip.extend(ZIP_LOCAL_HEADER);
+        zip.extend(&[20, 0, 0, 0]);
+        zip.extend(&method.to_le_bytes());
+        zip.extend(&[0; 8]);
+        zip.extend(&sizes);
+        zip.extend(&u16::try_from(name.len()).unwrap().to_le_bytes());
+        zip.extend(&[3, 0]);
+        zip.extend(name.as_bytes());
+        zip.extend(&[1, 2, 3]);
+        zip.extend(data);
+
+        directory.extend(ZIP_DIRECTORY_ENTRY);
+        directory.extend(&[20, 0, 20, 0, 0, 0]);
+        directory.extend(&method.to_le_bytes());
+        directory.extend(&[0; 8]);
+        directory.extend(&sizes);
+        directory.extend(&u16::try_from(name.len()).unwrap().to_le_bytes());
+        directory.extend(&[0; 12]);
+        directory.extend(&header_offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+
+    #[test]
+    fn listing_zip_members() {
+        let text = b"Deflated contents, deflated contents, deflated contents.";
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text).unwrap();
+        let deflated = encoder.finish().unwrap();
+
+        let mut zip = b"self-extracting stub".to_vec();
+        let mut directory = Vec::new();
+        zip_entry(&mut zip, &mut directory, "stored.txt", 0, b"Stored", 6);
+        zip_entry(&mut zip, &mut directory, "dir/", 0, b"", 0);
+        zip_entry(&mut zip, &mut directory, "dir/deflated.txt", 8, &deflated, text.len());
+        zip_entry(&mut zip, &mut directory, "odd.bin", 14, b"?", 1);
+        let directory_offset = u32::try_from(zip.len()).unwrap();
+        let directory_size = u32::try_from(directory.len()).unwrap();
+        zip.extend(directory);
+        zip.extend(ZIP_END_OF_DIRECTORY);
+        zip.extend(&[0, 0, 0, 0, 4, 0, 4, 0]);
+        zip.extend(&directory_size.to_le_bytes());
+        zip.extend(&directory_offset.to_le_bytes());
+        zip.extend(&[0, 0]);
+
+        let archive = Archive::read(&mut snapshot_of(&zip)).unwrap().unwrap();
+        assert_eq!(archive.kind, ArchiveKind::Zip);
+        let names: Vec<&str> = archive.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["stored.txt", "dir/deflated.txt", "odd.bin"]);
+        let stored = archive.member("stored.txt").unwrap();
+        assert_eq!((stored.offset, stored.size, stored.method), (20 + 30 + 10 + 3, 6, Method::Stored));
+        assert_eq!(extract(&zip, stored), b"Stored");
+        let deflated_member = archive.member("dir/deflated.txt").unwrap();
+        assert_eq!(deflated_member.stored_size, u64::try_from(deflated.len()).unwrap());
+        assert_eq!(extract(&zip, deflated_member), &text[..]);
+        assert!(archive.member("odd.bin").unwrap().open(snapshot_of(&zip)).is_err());
+    }
+
+    #[test]
+    fn sizes_larger_than_the_archive_are_rejected() {
+        let mut header = tar_header("././@LongLink", 0, b'L');
+        // A base-256 size of 2^64 - 1.
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&[0xFF; 8]);
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        header.extend(vec![0; 1024]);
+        let error = Archive::read(&mut snapshot_of(&header)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let mut ar = AR_MAGIC.to_vec();
+        ar.extend(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", "//", 0, 0, 0, 644, 9_999_999_999u64).as_bytes());
+        ar.extend(b"names/\n");
+        assert_eq!(Archive::read(&mut snapshot_of(&ar)).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn other_files_are_not_archives() {
+        assert!(Archive::read(&mut snapshot_of(b"")).unwrap().is_none());
+        assert!(Archive::read(&mut snapshot_of(&[b'x'; 2000])).unwrap().is_none());
+        let tmpf = tempfile::NamedTempFile::new().unwrap();
+        assert!(Archive::read(&mut Snapshot::new(File::open(tmpf.path()).unwrap()).unwrap()).unwrap().is_none());
+
+        // A file that happens to end with the signature of a zip end of directory record, which points nowhere.
+        let mut text = b"Not a zip archive.".to_vec();
+        text.extend(ZIP_END_OF_DIRECTORY);
+        text.extend(&[0, 0, 0, 0, 1, 0, 1, 0, 46, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0]);
+        assert!(Archive::read(&mut snapshot_of(&text)).unwrap().is_none());
+    }
+}
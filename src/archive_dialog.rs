@@ -0,0 +1,157 @@
+use std::rc::Rc;
+
+use cursive::event::Key;
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::views::{Dialog, DummyView, EditView, LinearLayout, OnEventView, SelectView, TextView};
+use cursive::Cursive;
+
+use crate::archive::{Archive, ArchiveKind, Member};
+use crate::byte_reader::TilingByteReader;
+use crate::decompress::Compression;
+use crate::hex_view::HexView;
+use crate::utilities::get_content;
+use crate::xxv_state::XxvState;
+use crate::xxv_tui::{confirm_unsaved_changes, replace_reader, ShowError, OBJ_ARCHIVE_FILTER, OBJ_ARCHIVE_LIST, OBJ_HEX_VIEW};
+
+pub fn open_archive_dialog(s: &mut Cursive) {
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        if v.input_progress().is_some_and(|p| !p.is_finished()) {
+            v.show_message("The archive can be listed when all of it has been read.".to_string());
+            return Ok(None);
+        }
+        let archive = Archive::read(&mut v.snapshot()?)?;
+        let is_raw_compressed = v.compression().is_none() && v.can_save() && Compression::of_file(&v.file_path())?.is_some();
+        if archive.is_none() && is_raw_compressed {
+            v.show_message("The file is compressed. Press z to decompress it, and then list its members.".to_string());
+        } else if archive.is_none() {
+            v.show_message("The file is not a zip, tar or ar archive.".to_string());
+        }
+        Ok(archive)
+    }).unwrap();
+    let archive = match result {
+        Ok(Some(archive)) => archive,
+        Ok(None) => return,
+        Err(error) => {
+            s.show_error(error);
+            return;
+        },
+    };
+    let kind = archive.kind;
+    let count_text = format!("{} members in the {} archive.", archive.members.len(), kind.name());
+    let rows = Rc::new(member_rows(archive.members));
+
+    let filter_rows = rows.clone();
+    let filter_field = EditView::new()
+        .on_edit(move |s, _, _| fill_list(s, &filter_rows))
+        .with_name(OBJ_ARCHIVE_FILTER)
+        .min_width(30);
+
+    let list: SelectView<Member> = SelectView::new().on_submit(move |s, member: &Member| extract_member(s, kind, member));
+
+    let layout = LinearLayout::vertical()
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("Filter: "))
+            .child(filter_field))
+        .child(DummyView)
+        .child(list.with_name(OBJ_ARCHIVE_LIST).scrollable().max_height(20))
+        .child(DummyView)
+        .child(TextView::new(count_text));
+
+    let dialog = Dialog::around(layout)
+        .button("Extract", move |s| with_selected_member(s, |s, member| extract_member(s, kind, member)))
+        .button("Go to data", |s| with_selected_member(s, go_to_data))
+        .dismiss_button("Close")
+        .title("Archive members");
+
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(esc_view);
+    fill_list(s, &rows);
+}
+
+/// Label each member with the offset of its data, its size, how it is stored, and its name.
+fn member_rows(members: Vec<Member>) -> Vec<(String, Member)> {
+    let offset_width = format!("{:X}", members.iter().map(|m| m.offset).max().unwrap_or(0)).len();
+    members.into_iter().map(|member| {
+        let label = format!(
+            "{:0ow$X} {:>12}  {:<9} {}",
+            member.offset, member.size, member.method.name(), member.name,
+            ow = offset_width);
+        (label, member)
+    }).collect()
+}
+
+fn fill_list(s: &mut Cursive, rows: &[(String, Member)]) {
+    let filter = s.call_on_name(OBJ_ARCHIVE_FILTER, get_content).unwrap().to_lowercase();
+    s.call_on_name(OBJ_ARCHIVE_LIST, |v: &mut SelectView<Member>| {
+        v.clear();
+        for (label, member) in rows {
+            if filter.is_empty() || label.to_lowercase().contains(&filter) {
+                v.add_item(label.clone(), member.clone());
+            }
+        }
+    });
+}
+
+fn with_selected_member<F: Fn(&mut Cursive, &Member)>(s: &mut Cursive, action: F) {
+    let selection = s.call_on_name(OBJ_ARCHIVE_LIST, |v: &mut SelectView<Member>| v.selection()).unwrap();
+    if let Some(member) = selection {
+        action(s, &member);
+    }
+}
+
+/// Select the bytes of the member in the archive, which are compressed if the member is.
+fn go_to_data(s: &mut Cursive, member: &Member) {
+    s.pop_layer();
+    s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.select_range(member.offset, member.stored_size));
+}
+
+fn extract_member(s: &mut Cursive, kind: ArchiveKind, member: &Member) {
+    s.pop_layer();
+    let member = member.clone();
+    confirm_unsaved_changes(s, move |s| open_member(s, kind, &member));
+}
+
+/// Extract the member with the name from the archive that is shown, such as a recent member of an archive that was just decompressed.
+pub fn extract_member_named(s: &mut Cursive, member_name: &str) {
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| Archive::read(&mut v.snapshot()?)).unwrap();
+    let archive = match result {
+        Ok(archive) => archive,
+        Err(error) => {
+            s.show_error(error);
+            return;
+        },
+    };
+    match archive.as_ref().and_then(|archive| archive.member(member_name).map(|member| (archive.kind, member))) {
+        Some((kind, member)) => open_member(s, kind, member),
+        None => {
+            let message = format!("{} is not in the archive.", member_name);
+            s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.show_message(message));
+        },
+    }
+}
+
+/// Show the extracted member instead of the archive.
+fn open_member(s: &mut Cursive, kind: ArchiveKind, member: &Member) {
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        let data = member.open(v.snapshot()?)?;
+        // Only members of archive files can be opened again later, not members of streams or of other members.
+        if v.can_save() || v.compression().is_some() {
+            TilingByteReader::extract(data, &v.file_path(), &member.name)
+        } else {
+            TilingByteReader::from_stream(data, format!("{} \u{25B8} {}", v.file_name(), member.name))
+        }
+    }).unwrap();
+    let b_reader = match result {
+        Ok(b_reader) => b_reader,
+        Err(error) => {
+            s.show_error(error);
+            return;
+        },
+    };
+    let message = format!("Opened {} from the {} archive", member.name, kind.name());
+    replace_reader(s, message, |state: &mut XxvState| state.restore_reader_state(b_reader));
+}
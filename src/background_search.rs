@@ -0,0 +1,92 @@
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use cursive::CbSink;
+
+use crate::byte_reader::Snapshot;
+use crate::file_search::{search, SearchListener};
+use crate::hex_view::HexView;
+use crate::replace_dialog::open_pending_replace_dialog;
+use crate::search_matcher::Matcher;
+use crate::xxv_tui::OBJ_HEX_VIEW;
+
+/// A batch of search results, sent from the search thread to the view.
+pub struct SearchResults {
+    pub generation: u64,
+    /// The offset and length of each match.
+    pub matches: Vec<(u64, u64)>,
+    pub searched: u64,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+pub struct SearchHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchHandle {
+    /// Ask the search thread to stop. The matches it has already found are still sent to the view.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Search the range of the snapshot on a new thread.
+/// The matches are sent to the hex view through the callback sink, in batches, together with the search progress.
+/// The generation tells the view which search the results belong to, so it can ignore results of old searches.
+pub fn spawn_search(snapshot: Snapshot, matcher: Box<dyn Matcher>, range: Range<u64>, generation: u64, sink: CbSink)
+    -> SearchHandle {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let mut sender = BatchSender {
+        generation,
+        matches: Vec::new(),
+        searched: 0,
+        cancelled: cancelled.clone(),
+        sink,
+    };
+    thread::spawn(move || {
+        let result = search(snapshot, matcher.as_ref(), range, &mut sender);
+        sender.send(true, result.err().map(|e| e.to_string()));
+    });
+    SearchHandle { cancelled }
+}
+
+struct BatchSender {
+    generation: u64,
+    matches: Vec<(u64, u64)>,
+    searched: u64,
+    cancelled: Arc<AtomicBool>,
+    sink: CbSink,
+}
+
+impl BatchSender {
+    /// Send the matches found since the last batch. Returns false if the UI is gone.
+    fn send(&mut self, finished: bool, error: Option<String>) -> bool {
+        let results = SearchResults {
+            generation: self.generation,
+            matches: std::mem::take(&mut self.matches),
+            searched: self.searched,
+            finished,
+            error,
+        };
+        self.sink.send(Box::new(move |s| {
+            s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.add_search_results(results));
+            if finished {
+                open_pending_replace_dialog(s);
+            }
+        })).is_ok()
+    }
+}
+
+impl SearchListener for BatchSender {
+    fn on_match(&mut self, offset: u64, length: u64) {
+        self.matches.push((offset, length));
+    }
+
+    fn on_progress(&mut self, searched: u64) -> bool {
+        self.searched = searched;
+        self.send(false, None) && !self.cancelled.load(Ordering::Relaxed)
+    }
+}
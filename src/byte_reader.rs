@@ -1,41 +1,272 @@
-use std::fs::File;
+use std::convert::TryFrom;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::BufWriter;
+use std::io::{Error, ErrorKind};
 use std::io::Read;
 use std::io::Result;
 use std::io::Seek;
 use std::io::SeekFrom;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::decompress::Compression;
+use crate::piece_table::{PieceTable, Source};
+use crate::process_memory::{Mapping, ProcessMemory};
+use crate::spool::{Spool, SpoolProgress};
+
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
 
 #[derive(Debug)]
 pub struct TilingByteReader {
     file: File,
     path: PathBuf,
     length: u64,
-    use_large_addresses: bool,
-    display_name: String
+    display_name: String,
+    edits: PieceTable,
+    kind: FileKind,
+    /// The input that is copied into the file, when reading from a stream rather than a file.
+    spool: Option<Spool>,
+    /// The mapped memory of a process, when reading its memory rather than a file.
+    memory: Option<Arc<ProcessMemory>>,
+    /// The compressed file and its format, when reading the decompressed contents of a file.
+    compressed: Option<(PathBuf, Compression)>,
+    /// The archive and the name of the member, when reading a member that was extracted from an archive.
+    member: Option<(PathBuf, String)>,
 }
 
 pub type Window = (u64, u64, u16, u16);
 
+/// Block devices and pseudo-files report a length of zero, or a length that is not what can be read,
+/// so their length is found in other ways.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FileKind {
+    File,
+    /// A disk or partition, whose length is found by seeking to its end.
+    BlockDevice,
+    /// A file in /proc or /sys, whose contents are made up when it is read, so its length is found by reading it.
+    PseudoFile,
+}
+
+impl FileKind {
+    fn of(path: &Path) -> Result<FileKind> {
+        let metadata = fs::metadata(path)?;
+        if is_block_device(&metadata) {
+            return Ok(FileKind::BlockDevice);
+        }
+        let is_proc_file = path.starts_with("/proc") && metadata.len() == 0;
+        if metadata.is_file() && (is_proc_file || path.starts_with("/sys")) {
+            return Ok(FileKind::PseudoFile);
+        }
+        Ok(FileKind::File)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileKind::File => "file",
+            FileKind::BlockDevice => "block device",
+            FileKind::PseudoFile => "pseudo-file",
+        }
+    }
+
+    fn length(&self, file: &mut File) -> Result<u64> {
+        match self {
+            FileKind::File => Ok(file.metadata()?.len()),
+            FileKind::BlockDevice => file.seek(SeekFrom::End(0)),
+            FileKind::PseudoFile => {
+                file.seek(SeekFrom::Start(0))?;
+                std::io::copy(file, &mut std::io::sink())
+            },
+        }
+    }
+}
+
 impl TilingByteReader {
+    /// Open the file, or read from stdin if the file name is `-`.
+    /// Pipes and sockets are read as streams, since they cannot be read at arbitrary offsets.
+    pub fn open<P: AsRef<Path>>(file_name: P) -> Result<TilingByteReader> {
+        let path = file_name.as_ref();
+        if path == Path::new("-") {
+            return TilingByteReader::from_stream(std::io::stdin(), "stdin".to_string());
+        }
+        if is_stream(path)? {
+            let display_name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into();
+            return TilingByteReader::from_stream(File::open(path)?, display_name);
+        }
+        TilingByteReader::new(path)
+    }
+    
     pub fn new<P: AsRef<Path>>(file_name: P) -> Result<TilingByteReader> {
         let path_buf = file_name.as_ref().canonicalize()?;
-        let display_name: String = path_buf.file_name().unwrap().to_string_lossy().into();
-        let file = File::open(file_name)?;
-        let file_len = file.metadata()?.len();
+        let mut display_name: String = path_buf.file_name().unwrap().to_string_lossy().into();
+        let kind = FileKind::of(&path_buf)?;
+        if kind != FileKind::File {
+            display_name = format!("{} [{}]", display_name, kind.name());
+        }
+        let mut file = File::open(file_name)?;
+        let file_len = kind.length(&mut file)?;
 
         Ok(TilingByteReader {
             file,
             path: path_buf,
             length: file_len,
-            use_large_addresses: file_len > u64::from(std::u32::MAX),
-            display_name
+            display_name,
+            edits: PieceTable::new(file_len),
+            kind,
+            spool: None,
+            memory: None,
+            compressed: None,
+            member: None,
         })
     }
     
-    pub fn reopen(&mut self) -> Result<()> {
+    /// Read from a stream, which is copied into a temporary file as it arrives.
+    /// The length grows as more input arrives, when `update_length` is called.
+    pub fn from_stream<R: Read + Send + 'static>(input: R, display_name: String) -> Result<TilingByteReader> {
+        let spool = Spool::start(input)?;
+        let file = File::open(spool.path())?;
+        Ok(TilingByteReader {
+            file,
+            path: spool.path().to_path_buf(),
+            length: 0,
+            display_name,
+            edits: PieceTable::new(0),
+            kind: FileKind::File,
+            spool: Some(spool),
+            memory: None,
+            compressed: None,
+            member: None,
+        })
+    }
+    
+    /// Read the decompressed contents of the file. They are decompressed into a temporary file as a stream.
+    pub fn decompress<P: AsRef<Path>>(file_name: P, compression: Compression) -> Result<TilingByteReader> {
+        let path_buf = file_name.as_ref().canonicalize()?;
+        let name: String = path_buf.file_name().unwrap().to_string_lossy().into();
+        let display_name = format!("{} \u{2192} {}", name, compression.decompressed_name(&name));
+        let decoder = compression.decoder(File::open(&path_buf)?)?;
+        let mut reader = TilingByteReader::from_stream(decoder, display_name)?;
+        reader.compressed = Some((path_buf, compression));
+        Ok(reader)
+    }
+    
+    /// Read a member of an archive, which is extracted into a temporary file as a stream.
+    pub fn extract<R: Read + Send + 'static>(data: R, archive_path: &Path, member_name: &str) -> Result<TilingByteReader> {
+        let archive_name = archive_path.file_name().unwrap_or(archive_path.as_os_str()).to_string_lossy();
+        let display_name = format!("{} \u{25B8} {}", archive_name, member_name);
+        let mut reader = TilingByteReader::from_stream(data, display_name)?;
+        reader.member = Some((archive_path.to_path_buf(), member_name.to_string()));
+        Ok(reader)
+    }
+    
+    /// Read the memory of a running process. Only the mapped ranges of its memory are shown, one after another.
+    pub fn from_process(pid: u32) -> Result<TilingByteReader> {
+        let memory = ProcessMemory::open(pid)?;
+        let file = File::open(memory.path())?;
+        let length = memory.len();
+        Ok(TilingByteReader {
+            file,
+            path: memory.path(),
+            length,
+            display_name: memory.display_name(),
+            edits: PieceTable::new(length),
+            kind: FileKind::File,
+            spool: None,
+            memory: Some(Arc::new(memory)),
+            compressed: None,
+            member: None,
+        })
+    }
+    
+    /// Add the input that has arrived since the last call, to the end of the file. Returns true if there was any.
+    pub fn update_length(&mut self) -> bool {
+        let received = match &self.spool {
+            Some(spool) => spool.progress().received(),
+            None => return false,
+        };
+        if received == self.length {
+            return false;
+        }
+        self.edits.append_original(received);
+        self.length = received;
+        true
+    }
+    
+    /// The progress of reading the input, if reading from a stream.
+    pub fn input_progress(&self) -> Option<&SpoolProgress> {
+        self.spool.as_ref().map(|spool| spool.progress())
+    }
+    
+    pub fn is_stream(&self) -> bool {
+        self.spool.is_some()
+    }
+    
+    pub fn is_process_memory(&self) -> bool {
+        self.memory.is_some()
+    }
+    
+    /// The format of the compressed file, when reading its decompressed contents.
+    pub fn compression(&self) -> Option<Compression> {
+        self.compressed.as_ref().map(|(_, compression)| *compression)
+    }
+    
+    /// The name of the archive member, when reading a member that was extracted from an archive.
+    pub fn member_name(&self) -> Option<&str> {
+        self.member.as_ref().map(|(_, name)| name.as_str())
+    }
+    
+    /// Whether edits can be saved to where the bytes were read from. Otherwise they can only be saved as a copy.
+    pub fn can_save(&self) -> bool {
+        !self.is_stream() && !self.is_process_memory()
+    }
+    
+    /// The memory mappings, when reading the memory of a process.
+    pub fn mappings(&self) -> &[Mapping] {
+        self.memory.as_ref().map_or(&[], |memory| memory.mappings())
+    }
+    
+    /// The virtual address of the byte at the given offset, when reading the memory of a process.
+    pub fn address_at(&self, offset: u64) -> Option<u64> {
+        self.memory.as_ref().and_then(|memory| memory.address_at(offset))
+    }
+    
+    /// Whether the byte at the given offset is in a page of process memory that could not be read.
+    pub fn is_unreadable(&self, offset: u64) -> bool {
+        match &self.memory {
+            Some(memory) => !self.edits.is_added(offset) && memory.is_unreadable(offset),
+            None => false,
+        }
+    }
+    
+    /// Re-open the file. Returns false if the file changed length, in which case all edits are discarded.
+    pub fn reopen(&mut self) -> Result<bool> {
+        if self.is_stream() {
+            // The input cannot be read again, but there may be more of it.
+            self.update_length();
+            return Ok(true);
+        }
+        if let Some(memory) = &self.memory {
+            // The process may have mapped or unmapped memory since it was last read.
+            let memory = ProcessMemory::open(memory.pid())?;
+            self.file = self.open_file()?;
+            let unchanged = memory.mappings() == self.mappings();
+            if !unchanged {
+                self.length = memory.len();
+                self.edits = PieceTable::new(self.length);
+            }
+            self.memory = Some(Arc::new(memory));
+            return Ok(unchanged);
+        }
         self.file = self.open_file()?;
-        self.length = self.file.metadata()?.len();
-        Ok(())
+        let file_len = self.kind.length(&mut self.file)?;
+        if file_len == self.length {
+            Ok(true)
+        } else {
+            self.length = file_len;
+            self.edits = PieceTable::new(file_len);
+            Ok(false)
+        }
     }
 
     pub fn open_file(&mut self) -> Result<File> {
@@ -46,8 +277,12 @@ impl TilingByteReader {
         &self.display_name
     }
     
+    /// The path of the file, or of the compressed file when reading its decompressed contents,
+    /// or of the archive when reading one of its members.
     pub fn get_path_clone(&self) -> PathBuf {
-        self.path.clone()
+        let compressed = self.compressed.as_ref().map(|(path, _)| path);
+        let archive = self.member.as_ref().map(|(path, _)| path);
+        compressed.or(archive).unwrap_or(&self.path).clone()
     }
 
     pub fn get_window(&mut self, window: Window, line_length: u64, buf: &mut Vec<u8>) -> Result<()> {
@@ -60,50 +295,268 @@ impl TilingByteReader {
         // The 'x' coordinate is the offset into each line, where the left-most window edge starts.
         // The 'h' height is the number of lines in the window,
         // and 'w' is the width of each window line.
+        // The lines are laid out over the edited file, so the offsets take inserted and deleted bytes
+        // into account.
         let (x, y, w, h) = window;
         let mut read_buf = vec![0; usize::from(w)];
 
         for i in y..(y + (u64::from(h))) {
             let offset = line_length * i + x;
-            self.file.seek(SeekFrom::Start(offset))?;
-            let bytes_read = self.file.read(&mut read_buf)?;
+            let bytes_read = self.read_at(offset, &mut read_buf)?;
             buf.extend(&read_buf[0..bytes_read]);
         }
         Ok(())
     }
     
+    /// Read the edited file contents at the given offset.
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let (file, memory) = (&mut self.file, self.memory.as_deref());
+        self.edits.read(offset, buf, |original_offset, dst| read_original(file, memory, original_offset, dst))
+    }
+    
+    pub fn read_byte(&mut self, offset: u64) -> Result<u8> {
+        let mut byte = [0];
+        if self.read_at(offset, &mut byte)? == 0 {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        Ok(byte[0])
+    }
+    
+    /// Replace `remove_length` bytes at the given offset with the given bytes.
+    /// The change is only kept in memory, until it is written to the file with `save`.
+    pub fn replace(&mut self, offset: u64, remove_length: u64, bytes: &[u8]) {
+        self.edits.replace(offset, remove_length, bytes);
+    }
+    
+    pub fn is_changed(&self, offset: u64) -> bool {
+        self.edits.is_added(offset)
+    }
+    
+    /// Write all pending changes to the file.
+    /// This is the only place where the file is opened for writing.
+    ///
+    /// If bytes were only overwritten, then only those bytes are written.
+    /// Otherwise the file is rewritten to a temporary file, which then replaces the original file.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.edits.has_edits() {
+            return Ok(());
+        }
+        if !self.can_save() {
+            let message = format!("{} is not a file, so the changes can only be saved as a copy.", self.display_name);
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        if self.edits.is_in_place() {
+            self.save_in_place()?;
+        } else if self.kind == FileKind::File {
+            self.save_by_rewrite()?;
+        } else {
+            let message = format!("Bytes cannot be inserted into or deleted from a {}.", self.kind.name());
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        self.length = self.kind.length(&mut self.file)?;
+        self.edits = PieceTable::new(self.length);
+        Ok(())
+    }
+    
+    /// Write the edited file contents to another file, and leave this file and its edits as they are.
+    pub fn save_copy(&mut self, path: &Path) -> Result<()> {
+        if let (Ok(target), Ok(source)) = (fs::canonicalize(path), fs::canonicalize(self.get_path_clone())) {
+            if target == source {
+                return Err(Error::new(ErrorKind::InvalidInput, "A copy cannot be saved over the file itself."));
+            }
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_edited(&mut writer)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+    }
+    
+    fn save_in_place(&mut self) -> Result<()> {
+        let mut file = OpenOptions::new().write(true).open(self.path.as_path())?;
+        let mut offset = 0;
+        for piece in self.edits.pieces() {
+            if piece.source == Source::Added {
+                file.seek(SeekFrom::Start(offset))?;
+                file.write_all(self.edits.added_bytes(piece))?;
+            }
+            offset += piece.length;
+        }
+        file.sync_all()
+    }
+    
+    fn save_by_rewrite(&mut self) -> Result<()> {
+        let mut temp_path = self.path.clone();
+        temp_path.set_file_name(format!(".{}.xv-save", self.display_name));
+        let permissions = self.file.metadata()?.permissions();
+        
+        let result = File::create(&temp_path).and_then(|temp_file| {
+            let mut writer = BufWriter::new(temp_file);
+            self.write_edited(&mut writer)?;
+            let temp_file = writer.into_inner().map_err(|e| e.into_error())?;
+            temp_file.sync_all()?;
+            fs::set_permissions(&temp_path, permissions)?;
+            fs::rename(&temp_path, &self.path)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+            return result;
+        }
+        self.file = self.open_file()?;
+        Ok(())
+    }
+    
+    /// Write the whole edited file contents to the given writer.
+    pub fn write_edited<W: Write>(&mut self, out: &mut W) -> Result<()> {
+        let mut buf = vec![0; COPY_BUFFER_SIZE];
+        for piece in self.edits.pieces() {
+            match piece.source {
+                Source::Added => out.write_all(self.edits.added_bytes(piece))?,
+                Source::Original => {
+                    let mut offset = piece.start;
+                    let end = piece.start + piece.length;
+                    while offset < end {
+                        let len = usize::try_from((end - offset).min(u64::try_from(buf.len()).unwrap())).unwrap();
+                        let bytes_read = read_original(&mut self.file, self.memory.as_deref(), offset, &mut buf[..len])?;
+                        if bytes_read == 0 {
+                            return Err(Error::from(ErrorKind::UnexpectedEof));
+                        }
+                        out.write_all(&buf[..bytes_read])?;
+                        offset += u64::try_from(bytes_read).unwrap();
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+    
+    /// Take a snapshot of the edited file contents, that can be read from another thread.
+    pub fn snapshot(&mut self) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new(self.open_file()?)?;
+        snapshot.edits = self.edits.clone();
+        snapshot.memory = self.memory.clone();
+        Ok(snapshot)
+    }
+    
     pub fn get_length(&self) -> u64 {
-        self.length
+        self.edits.len()
     }
     
     pub fn use_large_addresses(&self) -> bool {
-        self.use_large_addresses
+        match &self.memory {
+            Some(memory) => memory.max_address() > u64::from(u32::MAX),
+            None => self.get_length() > u64::from(u32::MAX),
+        }
     }
 }
 
+/// The file contents with the edits that were made when the snapshot was taken.
+/// Later edits, and saving the edits, do not change the contents of the snapshot.
+#[derive(Debug)]
+pub struct Snapshot {
+    file: File,
+    edits: PieceTable,
+    memory: Option<Arc<ProcessMemory>>,
+}
+
+impl Snapshot {
+    pub fn new(file: File) -> Result<Snapshot> {
+        let file_len = file.metadata()?.len();
+        Ok(Snapshot {
+            file,
+            edits: PieceTable::new(file_len),
+            memory: None,
+        })
+    }
+    
+    pub fn len(&self) -> u64 {
+        self.edits.len()
+    }
+    
+    pub fn has_edits(&self) -> bool {
+        self.edits.has_edits()
+    }
+    
+    pub fn is_process_memory(&self) -> bool {
+        self.memory.is_some()
+    }
+    
+    /// The underlying file, which has the same contents as the snapshot if there are no edits,
+    /// and it is not process memory.
+    pub fn file(&mut self) -> &mut File {
+        &mut self.file
+    }
+    
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let (file, memory) = (&mut self.file, self.memory.as_deref());
+        self.edits.read(offset, buf, |original_offset, dst| read_original(file, memory, original_offset, dst))
+    }
+}
+
+#[cfg(unix)]
+fn is_block_device(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_block_device()
+}
+
+#[cfg(not(unix))]
+fn is_block_device(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn is_stream(path: &Path) -> Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+    let file_type = fs::metadata(path)?.file_type();
+    Ok(file_type.is_fifo() || file_type.is_socket())
+}
+
+#[cfg(not(unix))]
+fn is_stream(_path: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+/// Read the unedited bytes, from the file, or from the mapped memory of a process.
+fn read_original(file: &mut File, memory: Option<&ProcessMemory>, offset: u64, buf: &mut [u8]) -> Result<usize> {
+    match memory {
+        Some(memory) => memory.read_at(file, offset, buf),
+        None => read_file_at(file, offset, buf),
+    }
+}
+
+fn read_file_at(file: &mut File, offset: u64, buf: &mut [u8]) -> Result<usize> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
 
-    use tempfile;
-
     use super::*;
 
     #[test]
     fn getting_top_left_window() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"01234567").unwrap();
+        tmpf.write_all(b"01234567").unwrap();
 
         let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
         let mut buf = Vec::new();
-        reader.get_window((0,0,16,16).into(), 16, &mut buf).unwrap();
+        reader.get_window((0,0,16,16), 16, &mut buf).unwrap();
         assert_eq!(buf, b"01234567")
     }
     
     #[test]
     fn getting_multi_line_string_top_left() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
         
         let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
         let mut buf = Vec::new();
@@ -114,7 +567,7 @@ mod tests {
     #[test]
     fn getting_multi_line_string_top_right() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
         
         let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
         let mut buf = Vec::new();
@@ -125,11 +578,122 @@ mod tests {
     #[test]
     fn getting_multi_line_string_bottom_left() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
         
         let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
         let mut buf = Vec::new();
         reader.get_window((0,1,4,2), 8, &mut buf).unwrap();
         assert_eq!(buf, b"89ab")
     }
+    
+    #[test]
+    fn saving_a_copy_leaves_the_file_unchanged() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789").unwrap();
+        let copy = tempfile::NamedTempFile::new().unwrap();
+
+        let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
+        reader.replace(2, 2, b"ab");
+        reader.save_copy(copy.path()).unwrap();
+        assert_eq!(fs::read(copy.path()).unwrap(), b"01ab456789");
+        assert_eq!(fs::read(tmpf.path()).unwrap(), b"0123456789");
+        assert!(reader.save_copy(tmpf.path()).is_err());
+    }
+    
+    #[test]
+    fn snapshots_are_not_affected_by_later_edits() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789").unwrap();
+
+        let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
+        reader.replace(0, 1, b"ab");
+        let mut snapshot = reader.snapshot().unwrap();
+        reader.replace(5, 5, b"");
+        assert!(snapshot.has_edits());
+        assert_eq!(snapshot.len(), 11);
+        let mut buf = [0; 11];
+        assert_eq!(snapshot.read_at(0, &mut buf).unwrap(), 11);
+        assert_eq!(&buf, b"ab123456789");
+    }
+    
+    #[test]
+    fn changes_are_kept_in_memory_until_saved() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+        
+        let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
+        reader.replace(1, 1, b"x");
+        reader.replace(2, 1, b"y");
+        reader.replace(9, 1, b"z");
+        let mut buf = Vec::new();
+        reader.get_window((0,0,4,3), 8, &mut buf).unwrap();
+        assert_eq!(buf, b"0xy38zab");
+        assert_eq!(reader.read_byte(9).unwrap(), b'z');
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"0123456789abcdef");
+        
+        reader.save().unwrap();
+        assert!(!reader.is_changed(1));
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"0xy345678zabcdef");
+    }
+    
+    #[test]
+    fn inserted_and_deleted_bytes_shift_the_layout() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+        
+        let mut reader = TilingByteReader::new(tmpf.path()).unwrap();
+        reader.replace(0, 0, b"xy");
+        reader.replace(6, 4, b"");
+        assert_eq!(reader.get_length(), 14);
+        let mut buf = Vec::new();
+        reader.get_window((0,0,4,4), 4, &mut buf).unwrap();
+        assert_eq!(buf, b"xy012389abcdef");
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"0123456789abcdef");
+        
+        reader.save().unwrap();
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"xy012389abcdef");
+        assert_eq!(reader.get_length(), 14);
+        assert!(!reader.is_changed(0));
+        buf.clear();
+        reader.get_window((0,0,4,4), 4, &mut buf).unwrap();
+        assert_eq!(buf, b"xy012389abcdef");
+    }
+    
+    #[test]
+    fn reading_from_a_stream() {
+        let mut reader = TilingByteReader::from_stream(std::io::Cursor::new(b"streamed".to_vec()), "stdin".to_string()).unwrap();
+        assert!(reader.is_stream());
+        let progress = reader.input_progress().unwrap().clone();
+        while !progress.is_finished() {
+            std::thread::yield_now();
+        }
+        assert_eq!(reader.get_length(), 0);
+        assert!(reader.update_length());
+        assert!(!reader.update_length());
+        reader.replace(0, 1, b"S");
+        let mut buf = Vec::new();
+        reader.get_window((0,0,4,2), 4, &mut buf).unwrap();
+        assert_eq!(buf, b"Streamed");
+        assert!(reader.save().is_err());
+        
+        let copy = tempfile::NamedTempFile::new().unwrap();
+        reader.save_copy(copy.path()).unwrap();
+        assert_eq!(fs::read(copy.path()).unwrap(), b"Streamed");
+    }
+    
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reading_a_pseudo_file() {
+        let contents = fs::read("/proc/self/mounts").unwrap();
+        assert_eq!(fs::metadata("/proc/self/mounts").unwrap().len(), 0);
+        
+        let mut reader = TilingByteReader::new("/proc/self/mounts").unwrap();
+        assert!(reader.file_name().ends_with(" [pseudo-file]"));
+        assert!(reader.get_length() > 0);
+        let mut buf = Vec::new();
+        reader.get_window((0,0,4,1), 4, &mut buf).unwrap();
+        assert_eq!(buf, &contents[..4]);
+        reader.replace(0, 1, b"");
+        assert!(reader.save().is_err());
+    }
 }
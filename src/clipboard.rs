@@ -0,0 +1,197 @@
+use std::convert::TryFrom;
+use std::env;
+use std::io::{Result, Write};
+use std::process::{Command, Stdio};
+
+use crate::hex_tables::BYTE_RENDER;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CopyFormat {
+    Hex,
+    SpacedHex,
+    CArray,
+    RustArray,
+    Base64,
+    Text,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 6] = [
+        CopyFormat::Hex,
+        CopyFormat::SpacedHex,
+        CopyFormat::CArray,
+        CopyFormat::RustArray,
+        CopyFormat::Base64,
+        CopyFormat::Text,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CopyFormat::Hex => "Hex",
+            CopyFormat::SpacedHex => "Spaced hex",
+            CopyFormat::CArray => "C array",
+            CopyFormat::RustArray => "Rust array",
+            CopyFormat::Base64 => "Base64",
+            CopyFormat::Text => "Visual text",
+        }
+    }
+
+    /// Render the bytes in this format.
+    /// The visual table is used for the text format, so the text looks like the visual column.
+    pub fn format(&self, bytes: &[u8], visual_table: &[&str; 256]) -> String {
+        match self {
+            CopyFormat::Hex => bytes.iter().map(|&b| BYTE_RENDER[usize::from(b)]).collect(),
+            CopyFormat::SpacedHex => {
+                let rendered: Vec<&str> = bytes.iter().map(|&b| BYTE_RENDER[usize::from(b)]).collect();
+                rendered.join(" ")
+            },
+            CopyFormat::CArray => array_literal(bytes, "{", "}"),
+            CopyFormat::RustArray => array_literal(bytes, "[", "]"),
+            CopyFormat::Base64 => base64(bytes),
+            CopyFormat::Text => bytes.iter().map(|&b| visual_table[usize::from(b)]).collect(),
+        }
+    }
+}
+
+fn array_literal(bytes: &[u8], open: &str, close: &str) -> String {
+    let mut literal = String::from(open);
+    if bytes.len() <= 16 {
+        let elements: Vec<String> = bytes.iter().map(|b| format!("0x{:02x}", b)).collect();
+        literal.push_str(&elements.join(", "));
+    } else {
+        // Long arrays are broken into lines of 16 elements, with a trailing comma.
+        literal.push('\n');
+        for line in bytes.chunks(16) {
+            let elements: Vec<String> = line.iter().map(|b| format!("0x{:02x},", b)).collect();
+            literal.push_str("    ");
+            literal.push_str(&elements.join(" "));
+            literal.push('\n');
+        }
+    }
+    literal.push_str(close);
+    literal
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = u32::from(chunk[0]);
+        let b1 = u32::from(*chunk.get(1).unwrap_or(&0));
+        let b2 = u32::from(*chunk.get(2).unwrap_or(&0));
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = usize::try_from((triple >> (18 - 6 * i)) & 0x3F).unwrap();
+                encoded.push(char::from(BASE64_ALPHABET[index]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Put the text on the clipboard.
+///
+/// The text is sent to the terminal as an OSC 52 escape sequence, which works over SSH and inside tmux,
+/// if the terminal supports it. Since there is no way to tell if the terminal understood the sequence,
+/// the text is also given to a local clipboard command, when we are not in a remote session.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut out = std::io::stdout();
+    out.write_all(osc52_sequence(text, env::var_os("TMUX").is_some()).as_bytes())?;
+    out.flush()?;
+
+    let remote = env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some();
+    if !remote {
+        copy_with_local_command(text);
+    }
+    Ok(())
+}
+
+fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if in_tmux {
+        // The plain sequence is handled by tmux itself when 'set-clipboard' is on,
+        // and the wrapped one is passed through to the outer terminal when 'allow-passthrough' is on.
+        format!("{}\x1bPtmux;{}\x1b\\", sequence, sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+fn copy_with_local_command(text: &str) {
+    let mut commands: Vec<&[&str]> = Vec::new();
+    if cfg!(target_os = "macos") {
+        commands.push(&["pbcopy"]);
+    }
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        commands.push(&["wl-copy"]);
+    }
+    if env::var_os("DISPLAY").is_some() {
+        commands.push(&["xclip", "-selection", "clipboard"]);
+        commands.push(&["xsel", "--clipboard", "--input"]);
+    }
+    for command in commands {
+        if run_copy_command(command, text).unwrap_or(false) {
+            break;
+        }
+    }
+}
+
+fn run_copy_command(command: &[&str], text: &str) -> Result<bool> {
+    let mut child = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(child.wait()?.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_tables::UNICODE_TEXT_TABLE;
+
+    use super::*;
+
+    #[test]
+    fn base64_encoding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xFF, 0xFE, 0x00, 0x3F]), "//4APw==");
+    }
+
+    #[test]
+    fn formatting_bytes() {
+        let bytes = b"Hi\n\xFF";
+        let format = |f: CopyFormat| f.format(bytes, UNICODE_TEXT_TABLE);
+        assert_eq!(format(CopyFormat::Hex), "48690aff");
+        assert_eq!(format(CopyFormat::SpacedHex), "48 69 0a ff");
+        assert_eq!(format(CopyFormat::CArray), "{0x48, 0x69, 0x0a, 0xff}");
+        assert_eq!(format(CopyFormat::RustArray), "[0x48, 0x69, 0x0a, 0xff]");
+        assert_eq!(format(CopyFormat::Base64), "SGkK/w==");
+        assert_eq!(format(CopyFormat::Text), format!("Hi\u{240A}{}", UNICODE_TEXT_TABLE[0xFF]));
+    }
+
+    #[test]
+    fn long_arrays_are_broken_into_lines() {
+        let bytes: Vec<u8> = (0..18).collect();
+        assert_eq!(CopyFormat::RustArray.format(&bytes, UNICODE_TEXT_TABLE), "[\n    \
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,\n    \
+            0x10, 0x11,\n]");
+    }
+
+    #[test]
+    fn osc52_is_wrapped_for_tmux() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(osc52_sequence("hi", true), "\x1b]52;c;aGk=\x07\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+}
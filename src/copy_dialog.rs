@@ -0,0 +1,85 @@
+use cursive::event::Key;
+use cursive::traits::{Resizable, Nameable};
+use cursive::views::{Dialog, DummyView, EditView, LinearLayout, OnEventView, SelectView, TextView};
+use cursive::Cursive;
+
+use crate::clipboard::{copy_to_clipboard, CopyFormat};
+use crate::hex_view::HexView;
+use crate::utilities::{get_content, parse_number_or_zero};
+use crate::xxv_tui::{ShowError, OBJ_HEX_VIEW, OBJ_COPY_OFFSET, OBJ_COPY_LENGTH, OBJ_COPY_FORMAT};
+
+/// Terminals limit the size of the OSC 52 sequences they accept, so there is no point in copying huge ranges.
+const MAX_COPY_LENGTH: u64 = 1024 * 1024;
+
+pub fn open_copy_dialog(s: &mut Cursive) {
+    let (offset, length) = s
+        .call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.selection_or_viewport())
+        .unwrap();
+
+    let edit_boxes = LinearLayout::vertical()
+        .child(
+            EditView::new()
+                .content(format!("0x{:X}", offset))
+                .with_name(OBJ_COPY_OFFSET)
+                .min_width(18),
+        )
+        .child(
+            EditView::new()
+                .content(format!("{}", length))
+                .with_name(OBJ_COPY_LENGTH)
+                .min_width(18),
+        );
+
+    let range_boxes = LinearLayout::horizontal()
+        .child(TextView::new("Offset:  \nLength:  "))
+        .child(edit_boxes);
+
+    let mut formats = SelectView::new();
+    for format in CopyFormat::ALL.iter() {
+        formats.add_item(format.name(), *format);
+    }
+    formats.set_on_submit(|s, _| do_copy(s));
+
+    let layout = LinearLayout::vertical()
+        .child(range_boxes)
+        .child(DummyView)
+        .child(TextView::new("Format:"))
+        .child(formats.with_name(OBJ_COPY_FORMAT));
+
+    let dialog = Dialog::around(layout)
+        .dismiss_button("Cancel")
+        .button("Copy", do_copy)
+        .title("Copy");
+
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        })
+        .on_event(Key::Enter, do_copy);
+
+    s.add_layer(esc_view);
+}
+
+fn do_copy(s: &mut Cursive) {
+    let offset_str = s.call_on_name(OBJ_COPY_OFFSET, get_content).unwrap();
+    let length_str = s.call_on_name(OBJ_COPY_LENGTH, get_content).unwrap();
+    let format = s
+        .call_on_name(OBJ_COPY_FORMAT, |v: &mut SelectView<CopyFormat>| v.selection())
+        .unwrap()
+        .map_or(CopyFormat::Hex, |f| *f);
+
+    s.pop_layer();
+
+    let offset = parse_number_or_zero(&offset_str);
+    let length = parse_number_or_zero(&length_str).min(MAX_COPY_LENGTH);
+
+    let result = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
+        let bytes = view.read_range(offset, length)?;
+        copy_to_clipboard(&format.format(&bytes, view.visual_table()))?;
+        view.show_message(format!("Copied {} bytes as {}", bytes.len(), format.name()));
+        Ok(())
+    }).unwrap();
+    if let Err(error) = result {
+        s.show_error(error);
+    }
+}
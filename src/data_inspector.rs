@@ -0,0 +1,243 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{Display, LowerExp};
+
+use time::OffsetDateTime;
+
+/// The number of bytes the inspector needs to decode every value; a LEB128 varint can be up to 10 bytes long.
+pub const INSPECT_LENGTH: usize = 10;
+
+/// Unix timestamps of 0000-01-01 and 9999-12-31 23:59:59, which is the range of timestamps we show as dates.
+const MIN_TIMESTAMP: i64 = -62_167_219_200;
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+
+/// Seconds from the Windows FILETIME epoch at 1601-01-01, to the Unix epoch.
+const FILETIME_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Decode the given bytes as numbers, characters and timestamps.
+/// The bytes start at the inspected offset, and are shorter than `INSPECT_LENGTH` near the end of the file.
+/// Values that need more bytes than are available are shown as "-".
+pub fn inspect(bytes: &[u8]) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    let le = |n| bytes.get(..n).map(|b: &[u8]| b.to_vec());
+    let be = |n| bytes.get(..n).map(|b: &[u8]| b.iter().rev().copied().collect::<Vec<u8>>());
+
+    rows.push(("binary", show(bytes.first().map(|b| format!("{:08b}", b)))));
+    rows.push(("i8", show(bytes.first().map(|&b| format!("{}", i8::from_le_bytes([b]))))));
+    rows.push(("u8", show(bytes.first().map(|b| format!("{}", b)))));
+    for &(name, n, little) in &[
+        ("i16 LE", 2, true), ("i16 BE", 2, false), ("u16 LE", 2, true), ("u16 BE", 2, false),
+        ("i32 LE", 4, true), ("i32 BE", 4, false), ("u32 LE", 4, true), ("u32 BE", 4, false),
+        ("i64 LE", 8, true), ("i64 BE", 8, false), ("u64 LE", 8, true), ("u64 BE", 8, false),
+    ] {
+        let value = if little { le(n) } else { be(n) };
+        let signed = name.starts_with('i');
+        rows.push((name, show(value.map(|v| format_integer(&v, signed)))));
+    }
+    let f32_value = |v: Vec<u8>| format_float(f32::from_le_bytes(array(&v)));
+    let f64_value = |v: Vec<u8>| format_float(f64::from_le_bytes(array(&v)));
+    rows.push(("f32 LE", show(le(4).map(f32_value))));
+    rows.push(("f32 BE", show(be(4).map(f32_value))));
+    rows.push(("f64 LE", show(le(8).map(f64_value))));
+    rows.push(("f64 BE", show(be(8).map(f64_value))));
+    rows.push(("ULEB128", show(read_uleb128(bytes).map(|v| format!("{}", v)))));
+    rows.push(("SLEB128", show(read_sleb128(bytes).map(|v| format!("{}", v)))));
+    rows.push(("UTF-8", show(decode_utf8(bytes).map(format_char))));
+    rows.push(("UTF-16 LE", show(decode_utf16(bytes, u16::from_le_bytes).map(format_char))));
+    rows.push(("UTF-16 BE", show(decode_utf16(bytes, u16::from_be_bytes).map(format_char))));
+    let unix_seconds = |v: Vec<u8>| format_timestamp(i64::from(u32::from_le_bytes(array(&v))));
+    let unix_millis = |v: Vec<u8>| format_timestamp(i64::from_le_bytes(array(&v)).div_euclid(1000));
+    let filetime = |v: Vec<u8>| format_filetime(u64::from_le_bytes(array(&v)));
+    let dos_date_time = |v: Vec<u8>| format_dos_date_time(u32::from_le_bytes(array(&v)));
+    rows.push(("Unix s", show(le(4).map(unix_seconds))));
+    rows.push(("Unix ms", show(le(8).map(unix_millis))));
+    rows.push(("FILETIME", show(le(8).map(filetime))));
+    rows.push(("DOS time", show(le(4).map(dos_date_time))));
+    rows
+}
+
+fn show(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "-".to_string())
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes.try_into().unwrap()
+}
+
+fn format_integer(little_endian: &[u8], signed: bool) -> String {
+    let negative = signed && little_endian.last().is_some_and(|b| b & 0x80 != 0);
+    // Narrower integers are sign-extended to 64 bits.
+    let mut buf = if negative { [0xFF; 8] } else { [0; 8] };
+    buf[..little_endian.len()].copy_from_slice(little_endian);
+    if signed {
+        format!("{}", i64::from_le_bytes(buf))
+    } else {
+        format!("{}", u64::from_le_bytes(buf))
+    }
+}
+
+fn format_float<F: Into<f64> + Display + LowerExp + Copy>(value: F) -> String {
+    let magnitude = value.into().abs();
+    if magnitude != 0.0 && magnitude.is_finite() && !(1e-5..1e16).contains(&magnitude) {
+        format!("{:e}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn read_uleb128(bytes: &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, &b) in bytes.iter().take(INSPECT_LENGTH).enumerate() {
+        value |= u64::from(b & 0x7F).checked_shl(7 * u32::try_from(i).unwrap())?;
+        if b & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_sleb128(bytes: &[u8]) -> Option<i64> {
+    let mut value = 0i64;
+    for (i, &b) in bytes.iter().take(INSPECT_LENGTH).enumerate() {
+        let shift = 7 * u32::try_from(i).unwrap();
+        value |= i64::from(b & 0x7F).checked_shl(shift)?;
+        if b & 0x80 == 0 {
+            if shift + 7 < 64 && b & 0x40 != 0 {
+                value |= -1i64 << (shift + 7);
+            }
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn decode_utf8(bytes: &[u8]) -> Option<char> {
+    let length = match bytes.first()? {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return None,
+    };
+    std::str::from_utf8(bytes.get(..length)?).ok()?.chars().next()
+}
+
+fn decode_utf16(bytes: &[u8], decode: fn([u8; 2]) -> u16) -> Option<char> {
+    let units: Vec<u16> = bytes.chunks_exact(2).take(2).map(|c| decode(array(c))).collect();
+    std::char::decode_utf16(units).next()?.ok()
+}
+
+fn format_char(c: char) -> String {
+    format!("{:?} U+{:04X}", c, u32::from(c))
+}
+
+fn format_timestamp(seconds: i64) -> String {
+    if (MIN_TIMESTAMP..=MAX_TIMESTAMP).contains(&seconds) {
+        OffsetDateTime::from_unix_timestamp(seconds).format("%F %T")
+    } else {
+        "out of range".to_string()
+    }
+}
+
+fn format_filetime(ticks: u64) -> String {
+    // FILETIME counts 100 nanosecond intervals.
+    let seconds = i64::try_from(ticks / 10_000_000).unwrap();
+    format_timestamp(seconds - FILETIME_EPOCH_OFFSET)
+}
+
+fn format_dos_date_time(value: u32) -> String {
+    // The time is in the low 16 bits, and the date in the high 16 bits, as they are stored in FAT and ZIP.
+    let time = value & 0xFFFF;
+    let date = value >> 16;
+    let year = 1980 + (date >> 9);
+    let month = (date >> 5) & 0xF;
+    let day = date & 0x1F;
+    let hour = time >> 11;
+    let minute = (time >> 5) & 0x3F;
+    let second = (time & 0x1F) * 2;
+    if month == 0 || month > 12 || day == 0 || hour > 23 || minute > 59 || second > 59 {
+        return "invalid".to_string();
+    }
+    format!("{}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(rows: &[(&'static str, String)], name: &str) -> String {
+        rows.iter().find(|(n, _)| *n == name).unwrap().1.clone()
+    }
+
+    #[test]
+    fn integers_in_both_endiannesses() {
+        let rows = inspect(&[0xFF, 0xFE, 0x00, 0x01, 0x02, 0x03, 0x04, 0x85]);
+        assert_eq!(value(&rows, "binary"), "11111111");
+        assert_eq!(value(&rows, "i8"), "-1");
+        assert_eq!(value(&rows, "u8"), "255");
+        assert_eq!(value(&rows, "i16 LE"), "-257");
+        assert_eq!(value(&rows, "u16 LE"), "65279");
+        assert_eq!(value(&rows, "i16 BE"), "-2");
+        assert_eq!(value(&rows, "u16 BE"), "65534");
+        assert_eq!(value(&rows, "u32 LE"), "16842495");
+        assert_eq!(value(&rows, "u32 BE"), "4294836225");
+        assert_eq!(value(&rows, "i64 LE"), "-8861954859616633089");
+        assert_eq!(value(&rows, "u64 BE"), "18446181128084849797");
+    }
+
+    #[test]
+    fn values_past_the_end_of_the_file() {
+        let rows = inspect(&[0x41, 0x00]);
+        assert_eq!(value(&rows, "u16 LE"), "65");
+        assert_eq!(value(&rows, "u32 LE"), "-");
+        assert_eq!(value(&rows, "f64 BE"), "-");
+        assert_eq!(value(&rows, "UTF-8"), "'A' U+0041");
+        assert_eq!(value(&rows, "UTF-16 LE"), "'A' U+0041");
+        assert!(inspect(&[]).iter().all(|(_, v)| v == "-"));
+    }
+
+    #[test]
+    fn floats() {
+        let rows = inspect(&1.5f64.to_be_bytes());
+        assert_eq!(value(&rows, "f64 BE"), "1.5");
+        let rows = inspect(&(-2.5e20f32).to_le_bytes());
+        assert_eq!(value(&rows, "f32 LE"), "-2.5e20");
+    }
+
+    #[test]
+    fn varints() {
+        let rows = inspect(&[0xE5, 0x8E, 0x26]);
+        assert_eq!(value(&rows, "ULEB128"), "624485");
+        let rows = inspect(&[0xC0, 0xBB, 0x78]);
+        assert_eq!(value(&rows, "SLEB128"), "-123456");
+        let rows = inspect(&[0x80, 0x80]);
+        assert_eq!(value(&rows, "ULEB128"), "-");
+    }
+
+    #[test]
+    fn characters() {
+        let rows = inspect("€".as_bytes());
+        assert_eq!(value(&rows, "UTF-8"), "'€' U+20AC");
+        let rows = inspect(&[0xD8, 0x3D, 0xDE, 0x00]);
+        assert_eq!(value(&rows, "UTF-16 BE"), "'😀' U+1F600");
+        let rows = inspect(&[0x3D, 0xD8]);
+        assert_eq!(value(&rows, "UTF-16 LE"), "-");
+        let rows = inspect(b"\n");
+        assert_eq!(value(&rows, "UTF-8"), "'\\n' U+000A");
+    }
+
+    #[test]
+    fn timestamps() {
+        let rows = inspect(&1_600_000_000u32.to_le_bytes());
+        assert_eq!(value(&rows, "Unix s"), "2020-09-13 12:26:40");
+        let rows = inspect(&1_600_000_000_123i64.to_le_bytes());
+        assert_eq!(value(&rows, "Unix ms"), "2020-09-13 12:26:40");
+        let rows = inspect(&132_444_736_000_000_000u64.to_le_bytes());
+        assert_eq!(value(&rows, "FILETIME"), "2020-09-13 12:26:40");
+        let rows = inspect(&[0x2D, 0x63, 0x2D, 0x51]);
+        assert_eq!(value(&rows, "DOS time"), "2020-09-13 12:25:26");
+        let rows = inspect(&[0xFF; 8]);
+        assert_eq!(value(&rows, "Unix ms"), "1969-12-31 23:59:59");
+        assert_eq!(value(&rows, "FILETIME"), "out of range");
+        assert_eq!(value(&rows, "DOS time"), "invalid");
+    }
+}
@@ -0,0 +1,205 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{Error, Read, Result};
+use std::path::Path;
+
+/// The number of bytes at the start of a file that are needed to recognise its compression format.
+const MAGIC_LENGTH: usize = 10;
+
+/// The magic number that starts every compressed block of a bzip2 stream, which is the BCD of pi.
+const BZIP2_BLOCK_MAGIC: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+
+/// Decompressed contents are written to a temporary file, so decompression stops at this length,
+/// rather than fill up the disk with a small file that decompresses to a huge one.
+pub const MAX_DECOMPRESSED_LENGTH: u64 = 4 * 1024 * 1024 * 1024;
+
+/// The compression formats that are recognised by their magic bytes, and can be decompressed when a file is opened.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    const ALL: [Compression; 4] = [Compression::Gzip, Compression::Xz, Compression::Zstd, Compression::Bzip2];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        }
+    }
+
+    fn has_magic(&self, bytes: &[u8]) -> bool {
+        match self {
+            Compression::Gzip => bytes.starts_with(&[0x1F, 0x8B]),
+            Compression::Xz => bytes.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]),
+            Compression::Zstd => bytes.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]),
+            // "BZh" is followed by the block size from 1 to 9, and the magic number of the first block.
+            Compression::Bzip2 => bytes.len() >= MAGIC_LENGTH
+                && bytes.starts_with(b"BZh")
+                && (b'1'..=b'9').contains(&bytes[3])
+                && bytes[4..MAGIC_LENGTH] == BZIP2_BLOCK_MAGIC,
+        }
+    }
+
+    /// The file name extensions of the format, and what they become when the file is decompressed.
+    fn extensions(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Compression::Gzip => &[(".gz", ""), (".tgz", ".tar")],
+            Compression::Xz => &[(".xz", ""), (".txz", ".tar")],
+            Compression::Zstd => &[(".zst", ""), (".tzst", ".tar")],
+            Compression::Bzip2 => &[(".bz2", ""), (".tbz2", ".tar"), (".tbz", ".tar")],
+        }
+    }
+
+    pub fn of_bytes(bytes: &[u8]) -> Option<Compression> {
+        Compression::ALL.iter().copied().find(|compression| compression.has_magic(bytes))
+    }
+
+    /// Look at the first bytes of the file, to see if it is compressed.
+    /// Only regular files are looked at, since reading from a stream would take the bytes away from it.
+    pub fn of_file(path: &Path) -> Result<Option<Compression>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let mut magic = Vec::with_capacity(MAGIC_LENGTH);
+        File::open(path)?.take(u64::try_from(MAGIC_LENGTH).unwrap()).read_to_end(&mut magic)?;
+        Ok(Compression::of_bytes(&magic))
+    }
+
+    /// The name of the file when it is decompressed, such as `log.txt` for `log.txt.gz`.
+    pub fn decompressed_name(&self, file_name: &str) -> String {
+        for (extension, replacement) in self.extensions() {
+            if let Some(stem) = file_name.strip_suffix(extension).filter(|stem| !stem.is_empty()) {
+                return format!("{}{}", stem, replacement);
+            }
+        }
+        format!("{} (decompressed)", file_name)
+    }
+
+    /// Decompress the input, up to `MAX_DECOMPRESSED_LENGTH` bytes.
+    pub fn decoder<R: Read + Send + 'static>(&self, input: R) -> Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::Gzip => limit_decompressed(flate2::read::MultiGzDecoder::new(input)),
+            Compression::Xz => limit_decompressed(xz2::read::XzDecoder::new_multi_decoder(input)),
+            Compression::Zstd => limit_decompressed(zstd::stream::read::Decoder::new(input)?),
+            Compression::Bzip2 => limit_decompressed(bzip2::read::MultiBzDecoder::new(input)),
+        })
+    }
+}
+
+/// Stop reading decompressed data at `MAX_DECOMPRESSED_LENGTH` bytes, with an error if there is more.
+pub fn limit_decompressed<R: Read + Send + 'static>(decompressed: R) -> Box<dyn Read + Send> {
+    Box::new(LimitedReader { input: decompressed, left: MAX_DECOMPRESSED_LENGTH })
+}
+
+struct LimitedReader<R> {
+    input: R,
+    left: u64,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.left == 0 {
+            // Only fail if there is more data, rather than when the data is exactly as long as the limit.
+            let mut byte = [0];
+            return match self.input.read(&mut byte)? {
+                0 => Ok(0),
+                _ => {
+                    let gib = MAX_DECOMPRESSED_LENGTH / (1024 * 1024 * 1024);
+                    let message = format!("Only the first {} GiB of the decompressed data are shown.", gib);
+                    Err(Error::other(message))
+                },
+            };
+        }
+        let length = usize::try_from(self.left).unwrap_or(usize::MAX).min(buf.len());
+        let bytes_read = self.input.read(&mut buf[..length])?;
+        self.left -= u64::try_from(bytes_read).unwrap();
+        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const CONTENTS: &[u8] = b"Compressed contents, compressed contents.";
+
+    fn compress(compression: Compression) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(CONTENTS).unwrap();
+                encoder.finish().unwrap()
+            },
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(CONTENTS).unwrap();
+                encoder.finish().unwrap()
+            },
+            Compression::Zstd => zstd::encode_all(CONTENTS, 0).unwrap(),
+            Compression::Bzip2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(CONTENTS).unwrap();
+                encoder.finish().unwrap()
+            },
+        }
+    }
+
+    #[test]
+    fn detecting_and_decompressing_every_format() {
+        for &compression in Compression::ALL.iter() {
+            let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+            tmpf.write_all(&compress(compression)).unwrap();
+
+            assert_eq!(Compression::of_file(tmpf.path()).unwrap(), Some(compression), "{}", compression.name());
+            let mut decompressed = Vec::new();
+            compression.decoder(File::open(tmpf.path()).unwrap()).unwrap().read_to_end(&mut decompressed).unwrap();
+            assert_eq!(decompressed, CONTENTS, "{}", compression.name());
+        }
+    }
+
+    #[test]
+    fn uncompressed_files_are_not_detected() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"B").unwrap();
+        assert_eq!(Compression::of_file(tmpf.path()).unwrap(), None);
+        assert_eq!(Compression::of_bytes(b""), None);
+        assert_eq!(Compression::of_bytes(b"\x7fELF"), None);
+        assert_eq!(Compression::of_file(Path::new("-")).unwrap(), None);
+        // Text that starts like a bzip2 header, without the magic number of a block.
+        assert_eq!(Compression::of_bytes(b"BZh9 is not compressed"), None);
+        assert_eq!(Compression::of_bytes(b"BZh0\x31\x41\x59\x26\x53\x59"), None);
+        assert_eq!(Compression::of_bytes(b"BZh1\x31\x41\x59\x26\x53\x59"), Some(Compression::Bzip2));
+    }
+
+    #[test]
+    fn decompressed_data_is_limited() {
+        let mut limited = LimitedReader { input: &b"0123456789"[..], left: 4 };
+        let mut buf = [0; 8];
+        assert_eq!(limited.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"0123");
+        assert!(limited.read(&mut buf).is_err());
+
+        let mut exact = LimitedReader { input: &b"0123"[..], left: 4 };
+        let mut contents = Vec::new();
+        exact.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, b"0123");
+    }
+
+    #[test]
+    fn naming_decompressed_files() {
+        assert_eq!(Compression::Gzip.decompressed_name("log.txt.gz"), "log.txt");
+        assert_eq!(Compression::Gzip.decompressed_name("backup.tgz"), "backup.tar");
+        assert_eq!(Compression::Zstd.decompressed_name("data.tar.zst"), "data.tar");
+        assert_eq!(Compression::Bzip2.decompressed_name("image"), "image (decompressed)");
+        assert_eq!(Compression::Xz.decompressed_name(".xz"), ".xz (decompressed)");
+    }
+}
@@ -0,0 +1,181 @@
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub offset: u64,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+impl Edit {
+    pub fn new(offset: u64, old: Vec<u8>, new: Vec<u8>) -> Edit {
+        Edit { offset, old, new }
+    }
+
+    /// The edit that reverts this edit.
+    pub fn inverse(&self) -> Edit {
+        Edit {
+            offset: self.offset,
+            old: self.new.clone(),
+            new: self.old.clone(),
+        }
+    }
+}
+
+/// The multi-level undo and redo stacks of the edits made to a file.
+/// Each entry is a group of edits that are undone and redone together, like the replacements of all search matches.
+#[derive(Debug)]
+pub struct EditHistory {
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+    saved_at: Option<usize>,
+}
+
+impl EditHistory {
+    pub fn new() -> EditHistory {
+        EditHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            saved_at: Some(0),
+        }
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        self.record_group(vec![edit]);
+    }
+
+    /// Record edits that are undone and redone as one.
+    pub fn record_group(&mut self, edits: Vec<Edit>) {
+        if edits.is_empty() {
+            return;
+        }
+        if let Some(saved_at) = self.saved_at {
+            if saved_at > self.undo.len() {
+                // The saved state was undone, and can no longer be reached by redoing.
+                self.saved_at = None;
+            }
+        }
+        self.redo.clear();
+        self.undo.push(edits);
+    }
+
+    /// Replace the new bytes of the most recent edit, instead of recording a new edit.
+    /// This is used when a byte is written one half at a time.
+    pub fn amend_last(&mut self, new: Vec<u8>) {
+        let last_is_saved = self.saved_at == Some(self.undo.len());
+        if let Some(last) = self.undo.last_mut().and_then(|group| group.last_mut()) {
+            if last.new != new && last_is_saved {
+                // The saved edit is changed, so the saved state can no longer be reached.
+                self.saved_at = None;
+            }
+            last.new = new;
+        }
+    }
+
+    /// Take the most recent group of edits off the undo stack. The returned edits must be reverted, last one first.
+    pub fn undo(&mut self) -> Option<Vec<Edit>> {
+        let edits = self.undo.pop()?;
+        self.redo.push(edits.clone());
+        Some(edits)
+    }
+
+    /// Take the most recently undone group of edits off the redo stack. The returned edits must be re-applied in order.
+    pub fn redo(&mut self) -> Option<Vec<Edit>> {
+        let edits = self.redo.pop()?;
+        self.undo.push(edits.clone());
+        Some(edits)
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_at = Some(self.undo.len());
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.saved_at != Some(self.undo.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = EditHistory::new();
+        history.record(Edit::new(1, vec![1], vec![2]));
+        history.record(Edit::new(2, vec![3], vec![4]));
+        assert_eq!(history.undo_count(), 2);
+
+        assert_eq!(history.undo(), Some(vec![Edit::new(2, vec![3], vec![4])]));
+        assert_eq!(history.undo(), Some(vec![Edit::new(1, vec![1], vec![2])]));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo_count(), 2);
+
+        assert_eq!(history.redo(), Some(vec![Edit::new(1, vec![1], vec![2])]));
+        assert_eq!(history.undo_count(), 1);
+        assert_eq!(history.redo_count(), 1);
+
+        history.record(Edit::new(3, vec![5], vec![6]));
+        assert_eq!(history.redo_count(), 0);
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn groups_of_edits_are_undone_together() {
+        let mut history = EditHistory::new();
+        history.record(Edit::new(0, vec![1], vec![2]));
+        history.record_group(vec![Edit::new(4, vec![3], vec![5]), Edit::new(8, vec![3], vec![5])]);
+        history.record_group(Vec::new());
+        assert_eq!(history.undo_count(), 2);
+        assert_eq!(history.undo(), Some(vec![Edit::new(4, vec![3], vec![5]), Edit::new(8, vec![3], vec![5])]));
+        assert_eq!(history.undo(), Some(vec![Edit::new(0, vec![1], vec![2])]));
+        assert_eq!(history.redo_count(), 2);
+    }
+
+    #[test]
+    fn amending_last_edit() {
+        let mut history = EditHistory::new();
+        history.record(Edit::new(1, vec![0x00], vec![0xA0]));
+        history.amend_last(vec![0xAB]);
+        assert_eq!(history.undo(), Some(vec![Edit::new(1, vec![0x00], vec![0xAB])]));
+    }
+
+    #[test]
+    fn modified_relative_to_saved_state() {
+        let mut history = EditHistory::new();
+        assert!(!history.is_modified());
+        history.record(Edit::new(1, vec![1], vec![2]));
+        assert!(history.is_modified());
+        history.mark_saved();
+        assert!(!history.is_modified());
+        history.undo();
+        assert!(history.is_modified());
+        history.redo();
+        assert!(!history.is_modified());
+        history.undo();
+        history.record(Edit::new(2, vec![3], vec![4]));
+        assert!(history.is_modified());
+        history.undo();
+        assert!(history.is_modified());
+    }
+
+    #[test]
+    fn amending_saved_edit_is_a_modification() {
+        let mut history = EditHistory::new();
+        history.record(Edit::new(1, vec![0x00], vec![0xA0]));
+        history.mark_saved();
+        history.amend_last(vec![0xA0]);
+        assert!(!history.is_modified());
+        history.amend_last(vec![0xAB]);
+        assert!(history.is_modified());
+        history.undo();
+        history.redo();
+        assert!(history.is_modified());
+    }
+}
@@ -1,44 +1,73 @@
 use std::convert::TryFrom;
-use std::fs::File;
 use std::io::*;
+use std::ops::Range;
 
-use bstr::Finder;
+use crate::byte_reader::Snapshot;
+use crate::search_matcher::Matcher;
 
 const BUFFER_SIZE: usize = 1024 * 1024;
 
+pub trait SearchListener {
+    fn on_match(&mut self, offset: u64, length: u64);
+
+    /// Called after each searched block, with the number of bytes of the range searched so far.
+    /// Returning false stops the search.
+    fn on_progress(&mut self, searched: u64) -> bool;
+}
+
+/// Search the given range of offsets in the source. Only matches that fit entirely within the range are found.
 #[cfg(target_os = "linux")]
-pub fn search<F>(mut file: File, bytes: &[u8], mut consumer: F)
-    where F: FnMut(u64) {
-    if async_io_search(&mut file, &bytes, &mut consumer).is_err() {
-        sync_io_search(&mut file, &bytes, &mut consumer);
+pub fn search<L>(mut source: Snapshot, matcher: &dyn Matcher, range: Range<u64>, listener: &mut L) -> Result<()>
+    where L: SearchListener {
+    check_matcher(matcher)?;
+    let range = clamp_range(&source, range);
+    // Edits only exist in memory, and process memory is read one mapping at a time, so they cannot be read with io_uring.
+    // Blocks are read ahead of time with io_uring, so it cannot go back for a match that was cut short.
+    if source.has_edits() || source.is_process_memory() || !matcher.is_fixed_length()
+        || async_io_search(&mut source, matcher, range.clone(), listener).is_err() {
+        sync_io_search(&mut source, matcher, range, listener)?;
     }
+    Ok(())
 }
 
 #[cfg(not(target_os = "linux"))]
-pub fn search<F>(mut file: File, bytes: &[u8], mut consumer: F)
-    where F: FnMut(u64) {
-    sync_io_search(&mut file, &bytes, &mut consumer);
+pub fn search<L>(mut source: Snapshot, matcher: &dyn Matcher, range: Range<u64>, listener: &mut L) -> Result<()>
+    where L: SearchListener {
+    check_matcher(matcher)?;
+    let range = clamp_range(&source, range);
+    sync_io_search(&mut source, matcher, range, listener)
+}
+
+fn clamp_range(source: &Snapshot, range: Range<u64>) -> Range<u64> {
+    let end = range.end.min(source.len());
+    range.start.min(end)..end
+}
+
+fn check_matcher(matcher: &dyn Matcher) -> Result<()> {
+    let length = matcher.max_match_length();
+    if length == 0 || length > BUFFER_SIZE {
+        return Err(Error::new(ErrorKind::InvalidInput, "The search must be between 1 byte and 1 MiB long."));
+    }
+    Ok(())
 }
 
 #[cfg(target_os = "linux")]
-fn async_io_search<F>(file: &mut File, bytes: &[u8], consumer: &mut F) -> Result<()>
-    where F: FnMut(u64) {
+fn async_io_search<L>(source: &mut Snapshot, matcher: &dyn Matcher, range: Range<u64>, listener: &mut L) -> Result<()>
+    where L: SearchListener {
     use std::collections::VecDeque;
-    
-    let file_len = file.metadata()?.len();
-    if file_len <= u64::try_from(BUFFER_SIZE).unwrap() {
-        sync_io_search(file, bytes, consumer);
-        return Ok(());
-    }
-    
-    let finder = Finder::new(bytes);
-    let needle_size = bytes.len();
+
+    if range.end - range.start <= u64::try_from(BUFFER_SIZE).unwrap() {
+        return sync_io_search(source, matcher, range, listener);
+    }
+
+    let file = source.file();
+    let needle_size = matcher.max_match_length();
     let queue_depth = 32;
-    let mut read_pos = 0;
+    let mut read_pos = range.start;
     let config = rio::Config::default();
     let io = config.start()?;
     let buffers = vec![vec![0; BUFFER_SIZE]; queue_depth];
-    
+
     let mut queue = VecDeque::with_capacity(queue_depth);
     for buf in &buffers {
         let cqe = io.read_at(file, buf, read_pos);
@@ -47,81 +76,226 @@ fn async_io_search<F>(file: &mut File, bytes: &[u8], consumer: &mut F) -> Result
     }
 
     while let Some((cqe, pos, buf)) = queue.pop_front() {
-        let num_bytes = cqe.wait()?;
-        if num_bytes > needle_size {
+        // Bytes past the end of the range are read, but not searched.
+        let num_bytes = cqe.wait()?.min(usize::try_from(range.end.saturating_sub(pos)).unwrap_or(usize::MAX));
+        if num_bytes >= needle_size {
             let mut offset = 0;
-            while let Some(p) = finder.find(&buf[offset..num_bytes]) {
-                consumer(pos + u64::try_from(offset + p).unwrap());
-                offset += p + 1;
+            while let Some((p, len)) = matcher.find_at(&buf[..num_bytes], offset) {
+                listener.on_match(pos + u64::try_from(p).unwrap(), u64::try_from(len).unwrap());
+                offset = matcher.next_start(p, len);
+            }
+            let searched = range.end.min(pos + u64::try_from(num_bytes).unwrap()) - range.start;
+            if !listener.on_progress(searched) {
+                // The remaining reads are waited for when the queue is dropped.
+                return Ok(());
+            }
+            if read_pos < range.end {
+                let cqe = io.read_at(file, buf, read_pos);
+                queue.push_back((cqe, read_pos, buf));
+                read_pos += u64::try_from(BUFFER_SIZE - needle_size + 1).unwrap();
             }
-            let cqe = io.read_at(file, buf, read_pos);
-            queue.push_back((cqe, read_pos, buf));
-            read_pos += u64::try_from(BUFFER_SIZE - needle_size + 1).unwrap();
         }
     }
-    
+
+    listener.on_progress(range.end - range.start);
     Ok(())
 }
 
-fn sync_io_search<F>(file: &mut File, bytes: &[u8], consumer: &mut F)
-    where F: FnMut(u64) {
-    let finder = Finder::new(bytes);
-    let needle_size = bytes.len();
+fn sync_io_search<L>(source: &mut Snapshot, matcher: &dyn Matcher, range: Range<u64>, listener: &mut L) -> Result<()>
+    where L: SearchListener {
+    // Consecutive blocks overlap, so matches that span two blocks are found in the second block.
+    let overlap = matcher.max_match_length() - 1;
     let mut buf = vec![0; BUFFER_SIZE];
-    let mut num_bytes = file.read(&mut buf).unwrap();
-    let mut pos = 0;
-    
-    while num_bytes > needle_size {
+    let mut pos = range.start;
+
+    loop {
+        let wanted = usize::try_from(range.end - pos).unwrap_or(usize::MAX).min(BUFFER_SIZE);
+        let num_bytes = source.read_at(pos, &mut buf[..wanted])?;
+        let last_block = num_bytes < BUFFER_SIZE;
+        let haystack = &buf[..num_bytes];
+        // Matches that start in the overlap are left for the next block, so they are not found twice.
+        let mut next_pos = if last_block { num_bytes } else { num_bytes - overlap };
         let mut offset = 0;
-        while let Some(p) = finder.find(&buf[offset..num_bytes]) {
-            consumer(pos + u64::try_from(offset + p).unwrap());
-            offset += p + 1;
+        while let Some((p, len)) = matcher.find_at(haystack, offset) {
+            if !last_block && p >= next_pos {
+                break;
+            }
+            if !last_block && p > 0 && p + len == num_bytes && !matcher.is_fixed_length() {
+                // The match might continue past the end of the block, so search again from the start of the match.
+                next_pos = p;
+                break;
+            }
+            listener.on_match(pos + u64::try_from(p).unwrap(), u64::try_from(len).unwrap());
+            offset = matcher.next_start(p, len);
+        }
+        // The next block must not start inside a match we already reported.
+        pos += u64::try_from(next_pos.max(offset)).unwrap();
+        if last_block {
+            listener.on_progress(pos - range.start);
+            return Ok(());
+        }
+        if !listener.on_progress(pos - range.start) {
+            return Ok(());
         }
-        pos += u64::try_from(num_bytes - needle_size + 1).unwrap();
-        file.seek(SeekFrom::Start(pos)).unwrap();
-        num_bytes = file.read(&mut buf).unwrap();
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::fs::File;
     use std::io::{Seek, SeekFrom, Write};
 
+    use crate::search_matcher::{LiteralMatcher, MaskedMatcher, RegexMatcher};
+
     use super::*;
 
+    struct Collector {
+        matches: Vec<u64>,
+        progress: Vec<u64>,
+        stop_after: usize,
+    }
+
+    impl Collector {
+        fn new() -> Collector {
+            Collector { matches: Vec::new(), progress: Vec::new(), stop_after: usize::MAX }
+        }
+    }
+
+    impl SearchListener for Collector {
+        fn on_match(&mut self, offset: u64, _length: u64) {
+            self.matches.push(offset);
+        }
+
+        fn on_progress(&mut self, searched: u64) -> bool {
+            self.progress.push(searched);
+            self.progress.len() < self.stop_after
+        }
+    }
+
     #[test]
     fn searching_in_file() {
         let mut file = tempfile::tempfile().unwrap();
-        file.write(b"ababa").unwrap();
+        file.write_all(b"ababa").unwrap();
         file.seek(SeekFrom::Start(0)).unwrap();
 
-        let mut output: Vec<u64> = Vec::new();
-        search(file, b"aba", |hit| output.push(hit));
-        assert_eq!(output, vec![0, 2]);
+        let mut output = Collector::new();
+        search(Snapshot::new(file).unwrap(), &LiteralMatcher::new(b"aba"), 0..u64::MAX, &mut output).unwrap();
+        assert_eq!(output.matches, vec![0, 2]);
+        assert_eq!(output.progress, vec![5]);
     }
-    
+
+    #[test]
+    fn needle_as_long_as_the_file() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"aba").unwrap();
+
+        let mut output = Collector::new();
+        search(Snapshot::new(file).unwrap(), &LiteralMatcher::new(b"aba"), 0..u64::MAX, &mut output).unwrap();
+        assert_eq!(output.matches, vec![0]);
+    }
+
     #[test]
     fn sync_io_search_in_big_file() {
         let mut file = tempfile::tempfile().unwrap();
         prepare_big_file(&mut file);
 
-        let mut counter: i64 = 0;
-        sync_io_search(&mut file, b"Pokemon", &mut |_| counter += 1);
+        let mut output = Collector::new();
+        sync_io_search(&mut Snapshot::new(file).unwrap(), &LiteralMatcher::new(b"Pokemon"), 0..u64::MAX, &mut output).unwrap();
+
+        assert_eq!(output.matches.len(), 3);
+        assert_eq!(output.progress.last(), Some(&u64::try_from(BUFFER_SIZE * 2 + (BUFFER_SIZE >> 1)).unwrap()));
+    }
+
+    #[test]
+    fn stopping_search_early() {
+        let mut file = tempfile::tempfile().unwrap();
+        prepare_big_file(&mut file);
+
+        let mut output = Collector::new();
+        output.stop_after = 1;
+        search(Snapshot::new(file).unwrap(), &LiteralMatcher::new(b"Pokemon"), 0..u64::MAX, &mut output).unwrap();
 
-        assert_eq!(counter, 3);
+        assert_eq!(output.progress.len(), 1);
+        assert!(output.matches.len() < 3);
     }
-    
+
+    #[test]
+    fn searching_within_a_range() {
+        let mut file = tempfile::tempfile().unwrap();
+        prepare_big_file(&mut file);
+        let block_end = u64::try_from(BUFFER_SIZE).unwrap();
+        let search_range = |range: Range<u64>, file: &File| {
+            let mut output = Collector::new();
+            let snapshot = Snapshot::new(file.try_clone().unwrap()).unwrap();
+            search(snapshot, &LiteralMatcher::new(b"Pokemon"), range, &mut output).unwrap();
+            output
+        };
+
+        let output = search_range(block_end - 3..block_end + 10, &file);
+        assert_eq!(output.matches, vec![block_end - 3]);
+        assert_eq!(output.progress.last(), Some(&13));
+        let output = search_range(block_end / 2..block_end * 2, &file);
+        assert_eq!(output.matches, vec![block_end - 3, block_end + 8]);
+        assert_eq!(output.progress.last(), Some(&(block_end * 3 / 2)));
+        let output = search_range(block_end * 2..u64::MAX, &file);
+        assert_eq!(output.matches, vec![block_end * 5 / 2 - 7]);
+    }
+
+    #[test]
+    fn regex_matches_across_block_boundaries() {
+        let mut file = tempfile::tempfile().unwrap();
+        prepare_big_file(&mut file);
+        let block_end = u64::try_from(BUFFER_SIZE).unwrap();
+        let matches = |pattern: &str, file: &File| {
+            let mut output = Vec::new();
+            let mut listener = RangeCollector(&mut output);
+            search(Snapshot::new(file.try_clone().unwrap()).unwrap(), &RegexMatcher::new(pattern).unwrap(), 0..u64::MAX, &mut listener).unwrap();
+            output
+        };
+
+        assert_eq!(matches(r"P[a-z]+", &file), vec![
+            (block_end - 3, 7), (block_end + 5, 3), (block_end + 8, 7), (block_end * 5 / 2 - 7, 7)]);
+        // Without going back to the start of the match, this would be cut short at the end of the first block.
+        file.seek(SeekFrom::Start(block_end - 100_000)).unwrap();
+        file.write_all(&[b'x'; 200_000]).unwrap();
+        assert_eq!(matches(r"x+", &file), vec![(block_end - 100_000, 200_000)]);
+    }
+
+    #[test]
+    fn masked_matches_across_block_boundaries() {
+        let mut file = tempfile::tempfile().unwrap();
+        prepare_big_file(&mut file);
+        let block_end = u64::try_from(BUFFER_SIZE).unwrap();
+        let matcher = MaskedMatcher::new(b"P\0kemon", &[0xFF, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let mut output = Collector::new();
+        search(Snapshot::new(file).unwrap(), &matcher, 0..u64::MAX, &mut output).unwrap();
+        assert_eq!(output.matches, vec![block_end - 3, block_end + 8, block_end * 5 / 2 - 7]);
+    }
+
+    struct RangeCollector<'a>(&'a mut Vec<(u64, u64)>);
+
+    impl SearchListener for RangeCollector<'_> {
+        fn on_match(&mut self, offset: u64, length: u64) {
+            self.0.push((offset, length));
+        }
+
+        fn on_progress(&mut self, _searched: u64) -> bool {
+            true
+        }
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn async_io_search_in_big_file() {
         let mut file = tempfile::tempfile().unwrap();
         prepare_big_file(&mut file);
 
-        let mut counter: i64 = 0;
-        if async_io_search(&mut file, b"Pokemon", &mut |_| counter += 1).is_ok() {
+        let mut output = Collector::new();
+        if async_io_search(&mut Snapshot::new(file).unwrap(), &LiteralMatcher::new(b"Pokemon"), 0..u64::MAX, &mut output).is_ok() {
             // Only assert when no error happens.
             // We allow systems where io_uring is not available.
-            assert_eq!(counter, 3);
+            assert_eq!(output.matches.len(), 3);
         }
     }
 
@@ -129,9 +303,9 @@ mod tests {
         let file_len = u64::try_from(BUFFER_SIZE * 2 + (BUFFER_SIZE >> 1)).unwrap();
         file.set_len(file_len).unwrap();
         file.seek(SeekFrom::Start(u64::try_from(BUFFER_SIZE - 3).unwrap())).unwrap();
-        file.write(b"Pokemon PokPokemon").unwrap();
+        file.write_all(b"Pokemon PokPokemon").unwrap();
         file.seek(SeekFrom::Start(file_len - 7)).unwrap();
-        file.write(b"Pokemon").unwrap();
+        file.write_all(b"Pokemon").unwrap();
         file.seek(SeekFrom::Start(0)).unwrap();
     }
 }
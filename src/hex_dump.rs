@@ -0,0 +1,427 @@
+use std::convert::TryFrom;
+use std::ffi::OsString;
+use std::io::{BufRead, Error, ErrorKind, Read, Result, Write};
+use std::ops::Range;
+use std::thread;
+use std::time::Duration;
+
+use crate::hex_reader::{HexReader, HexVisitor, Highlight, OffsetsVisitor, VisualMode};
+use crate::hex_tables::{ByteCategory, BYTE_CATEGORY, BYTE_RENDER};
+use crate::hex_view_printers::GROUP_SEP;
+use crate::search_dialog::hex_to_bytes;
+use crate::utilities::parse_number;
+
+/// The number of lines that are read from the file at a time.
+const LINES_PER_CAPTURE: u64 = 256;
+const COLUMN_SEP: &str = "\u{2502}";
+const OFFSET_COLOR: &str = "\x1b[34m";
+const RESET_COLOR: &str = "\x1b[0m";
+
+/// The options of the `--dump` mode, which writes the file to stdout instead of starting the TUI.
+#[derive(Debug)]
+pub struct DumpOptions {
+    pub file: OsString,
+    offset: u64,
+    length: Option<u64>,
+    width: Option<u64>,
+    visual: Option<VisualMode>,
+    color: bool,
+    /// Dump the raw bytes of compressed files, instead of their decompressed contents.
+    pub raw: bool,
+}
+
+/// Parse the arguments that follow `--dump`.
+pub fn parse_dump_args<I: Iterator<Item = OsString>>(mut args: I) -> std::result::Result<DumpOptions, String> {
+    let mut file = None;
+    let mut options = DumpOptions {
+        file: OsString::new(),
+        offset: 0,
+        length: None,
+        width: None,
+        visual: None,
+        color: false,
+        raw: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            let value = args.next().ok_or(format!("The '{}' option needs a value.", name))?;
+            let value = value.to_string_lossy().to_string();
+            Ok::<_, String>(value)
+        };
+        let mut number = |name: &str| {
+            let text = value(name)?;
+            parse_number(&text).map_err(|e| format!("Not a number for '{}': {} ({})", name, text, e))
+        };
+        match arg.to_str() {
+            Some("--offset") => options.offset = number("--offset")?,
+            Some("--length") => options.length = Some(number("--length")?),
+            Some("--width") => {
+                let width = number("--width")?;
+                if width == 0 || width > u64::from(u16::MAX) {
+                    return Err(format!("The line width must be between 1 and {}.", u16::MAX));
+                }
+                options.width = Some(width);
+            },
+            Some("--visual") => {
+                options.visual = Some(match value("--visual")?.as_str() {
+                    "unicode" => VisualMode::Unicode,
+                    "ascii" => VisualMode::Ascii,
+                    "off" => VisualMode::Off,
+                    other => return Err(format!("Unknown visual mode: {}", other)),
+                });
+            },
+            Some("--color") => options.color = true,
+            Some("--raw") => options.raw = true,
+            _ if file.is_none() => file = Some(arg),
+            _ => return Err(format!("Unexpected argument: {}", arg.to_string_lossy())),
+        }
+    }
+    options.file = file.ok_or("The 'file' argument is required.")?;
+    Ok(options)
+}
+
+/// Write the offsets, hex and visual columns of the chosen range of the file, the same way they are shown in the TUI.
+/// The line width, group size and visual mode of the reader are used, unless they are overridden by the options.
+pub fn dump(reader: &mut HexReader, options: &DumpOptions, out: &mut dyn Write) -> Result<()> {
+    if let Some(width) = options.width {
+        reader.line_width = width;
+    }
+    if let Some(mode) = options.visual {
+        reader.set_visual_mode(mode);
+    }
+    // A stream is dumped once all of it has arrived.
+    while let Some(progress) = reader.input_progress() {
+        if progress.is_finished() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    if let Some(error) = reader.input_progress().and_then(|progress| progress.error()) {
+        return Err(Error::new(ErrorKind::InvalidData, error));
+    }
+    reader.update_length();
+    let line_width = reader.line_width;
+    let file_length = reader.get_length();
+    let start = options.offset.min(file_length);
+    let end = options.length.map_or(file_length, |length| start.saturating_add(length).min(file_length));
+    let show_visual = !matches!(reader.get_visual_mode(), VisualMode::Off);
+    let hex_width = usize::try_from(line_width * 3 - 1).unwrap();
+    if start == end {
+        return out.flush();
+    }
+
+    let mut line = start / line_width;
+    let end_line = end.div_ceil(line_width);
+    while line < end_line {
+        let lines = (end_line - line).min(LINES_PER_CAPTURE);
+        reader.window_pos = (0, line);
+        reader.window_size = (u16::try_from(line_width).unwrap(), u16::try_from(lines).unwrap());
+        reader.capture()?;
+
+        let mut offsets = OffsetLines { lines: Vec::new() };
+        reader.visit_row_offsets(&mut offsets);
+        let first_offset = line * line_width;
+        let mut hex = DumpLines::hex(first_offset, start..end, options.color);
+        reader.visit_hex(&mut hex);
+        let mut visual = DumpLines::visual(first_offset, start..end, reader.vis_table(), options.color);
+        if show_visual {
+            reader.visit_hex(&mut visual);
+        }
+
+        for (i, offset) in offsets.lines.iter().enumerate() {
+            let hex_line = hex.lines.get(i).map_or(("", 0), |(text, width)| (text.as_str(), *width));
+            let padding = hex_width.saturating_sub(hex_line.1);
+            if options.color {
+                write!(out, "{}{}{}", OFFSET_COLOR, offset, RESET_COLOR)?;
+            } else {
+                write!(out, "{}", offset)?;
+            }
+            write!(out, "{} {}{:padding$} {}", COLUMN_SEP, hex_line.0, "", COLUMN_SEP, padding = padding)?;
+            if let Some((text, _)) = visual.lines.get(i) {
+                write!(out, "{}", text.trim_end_matches(' '))?;
+            }
+            writeln!(out)?;
+        }
+        line += lines;
+    }
+    out.flush()
+}
+
+/// Turn a hex dump back into bytes. Each line can be in the layout of `--dump`, the layout of `xxd`, or plain hex.
+/// Lines with offsets are placed at their offset, and any gap before them is filled with zeros.
+/// Plain hex continues where the previous line ended.
+pub fn reverse(input: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    let mut position = 0;
+    for (number, line) in input.lines().enumerate() {
+        let line = strip_colors(&line?);
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, format!("Line {}: {}", number + 1, message));
+        let (offset, hex) = split_dump_line(&line).map_err(invalid)?;
+
+        if hex.contains('?') {
+            return Err(invalid("Wildcards cannot be turned into bytes.".to_string()));
+        }
+        let hex = hex.replace(GROUP_SEP, " ");
+        let bytes = match hex_to_bytes(&hex) {
+            Some(bytes) => bytes,
+            None => return Err(invalid(format!("Not whole hex bytes: {}", hex.trim()))),
+        };
+        if bytes.is_empty() {
+            continue;
+        }
+
+        let offset = offset.unwrap_or(position);
+        if offset < position {
+            return Err(invalid(format!("The offset 0x{:X} is before the end of the previous line.", offset)));
+        }
+        let mut zeros = std::io::repeat(0).take(offset - position);
+        std::io::copy(&mut zeros, out)?;
+        out.write_all(&bytes)?;
+        position = offset + u64::try_from(bytes.len()).unwrap();
+    }
+    out.flush()
+}
+
+/// Split a line of a hex dump into its offset, if it has one, and its hex column.
+fn split_dump_line(line: &str) -> std::result::Result<(Option<u64>, &str), String> {
+    let parse_offset = |text: &str, radix: u32| {
+        u64::from_str_radix(text.trim(), radix).map_err(|e| format!("Not an offset: {} ({})", text.trim(), e))
+    };
+    if let Some((offset, rest)) = line.split_once(COLUMN_SEP) {
+        // The layout of --dump, where the hex column may start with blanks for bytes that were left out.
+        let offset = parse_offset(offset.trim().trim_start_matches("0x"), 16)?;
+        let hex = rest.split(COLUMN_SEP).next().unwrap_or("");
+        let hex = hex.strip_prefix(' ').unwrap_or(hex);
+        let blanks = hex.chars().take_while(|&c| c == ' ' || GROUP_SEP.starts_with(c)).count();
+        return Ok((Some(offset + u64::try_from(blanks / 3).unwrap()), hex));
+    }
+    if let Some((offset, rest)) = line.split_once(": ") {
+        if !offset.is_empty() && offset.chars().all(|c| c.is_ascii_hexdigit()) {
+            // The layout of xxd, where the text column follows two spaces after the hex column.
+            let hex = rest.split("  ").next().unwrap_or("");
+            return Ok((Some(parse_offset(offset, 16)?), hex));
+        }
+    }
+    Ok((None, line))
+}
+
+/// Remove the ANSI color escape codes that `--dump --color` adds.
+fn strip_colors(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("\x1b[") {
+        stripped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        rest = match rest.find('m') {
+            Some(end) => &rest[end + 1..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+struct OffsetLines {
+    lines: Vec<String>,
+}
+
+impl OffsetsVisitor for OffsetLines {
+    fn offset(&mut self, offset: &str) {
+        self.lines.push(offset.to_string());
+    }
+
+    fn end(&mut self) {
+        // Nothing to do.
+    }
+}
+
+/// Renders the bytes of each line with the given table, and blanks out the bytes outside of the range.
+/// The lines are kept with their width, because the color escape codes take up no space on the screen.
+struct DumpLines {
+    offset: u64,
+    range: Range<u64>,
+    table: &'static [&'static str; 256],
+    color: bool,
+    is_hex: bool,
+    /// True if a group separator goes before the next byte, in the place of the space between them.
+    separated: bool,
+    line: String,
+    width: usize,
+    lines: Vec<(String, usize)>,
+}
+
+impl DumpLines {
+    fn hex(offset: u64, range: Range<u64>, color: bool) -> DumpLines {
+        DumpLines::new(offset, range, BYTE_RENDER, color, true)
+    }
+
+    fn visual(offset: u64, range: Range<u64>, table: &'static [&'static str; 256], color: bool) -> DumpLines {
+        DumpLines::new(offset, range, table, color, false)
+    }
+
+    fn new(offset: u64, range: Range<u64>, table: &'static [&'static str; 256], color: bool, is_hex: bool) -> DumpLines {
+        DumpLines {
+            offset,
+            range,
+            table,
+            color,
+            is_hex,
+            separated: false,
+            line: String::new(),
+            width: 0,
+            lines: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, text: &str, width: usize) {
+        self.line.push_str(text);
+        self.width += width;
+    }
+}
+
+impl HexVisitor for DumpLines {
+    fn byte(&mut self, index: usize, _highlight: Highlight) {
+        // The group separator is only added once the next byte is known, so no line ends with one.
+        if self.separated {
+            self.push(GROUP_SEP, 1);
+        } else if self.is_hex && self.width != 0 {
+            self.push(" ", 1);
+        }
+        self.separated = false;
+        let width = if self.is_hex { 2 } else { 1 };
+        let color = category_color(&BYTE_CATEGORY[index]);
+        if !self.range.contains(&self.offset) {
+            self.push(&" ".repeat(width), width);
+        } else if self.color && !color.is_empty() {
+            self.line.push_str(color);
+            self.push(self.table[index], width);
+            self.line.push_str(RESET_COLOR);
+        } else {
+            self.push(self.table[index], width);
+        }
+        self.offset += 1;
+    }
+
+    fn group(&mut self) {
+        self.separated = true;
+    }
+
+    fn next_line(&mut self) {
+        self.end();
+    }
+
+    fn end(&mut self) {
+        if !self.line.is_empty() {
+            self.lines.push((std::mem::take(&mut self.line), self.width));
+        }
+        self.width = 0;
+        self.separated = false;
+    }
+}
+
+/// The same colors as the TUI uses in its default theme.
+fn category_color(category: &ByteCategory) -> &'static str {
+    match category {
+        ByteCategory::AsciiControl => "\x1b[31m",
+        ByteCategory::AsciiPrintable => "",
+        ByteCategory::AsciiWhitespace => "\x1b[34m",
+        ByteCategory::Other => "\x1b[33m",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::byte_reader::TilingByteReader;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> std::result::Result<DumpOptions, String> {
+        parse_dump_args(args.iter().map(OsString::from))
+    }
+
+    fn dump_to_string(data: &[u8], options: &[&str]) -> String {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(data).unwrap();
+        let path = tmpf.path().to_str().unwrap();
+        let mut arguments = vec![path];
+        arguments.extend_from_slice(options);
+        let options = args(&arguments).unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let mut reader = HexReader::new(byte_reader).unwrap();
+        let mut out = Vec::new();
+        dump(&mut reader, &options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parsing_arguments() {
+        let options = args(&["--offset", "0x10", "file", "--length", "32", "--width", "8", "--color"]).unwrap();
+        assert_eq!(options.file, OsString::from("file"));
+        assert_eq!((options.offset, options.length, options.width, options.color), (16, Some(32), Some(8), true));
+        assert!(args(&[]).is_err());
+        assert!(args(&["file", "other"]).is_err());
+        assert!(args(&["file", "--offset"]).is_err());
+        assert!(args(&["file", "--width", "0"]).is_err());
+        assert!(args(&["file", "--visual", "fancy"]).is_err());
+    }
+
+    #[test]
+    fn dumping_a_file() {
+        let data = b"0123456789abcdefXYZ";
+        assert_eq!(dump_to_string(data, &[]), "\
+            0x00000000\u{2502} 30 31 32 33 34 35 36 37\u{A6}38 39 61 62 63 64 65 66 \u{2502}01234567\u{A6}89abcdef\n\
+            0x00000010\u{2502} 58 59 5a                                        \u{2502}XYZ\n");
+        assert_eq!(dump_to_string(data, &["--offset", "2", "--length", "7", "--width", "4", "--visual", "off"]), "\
+            0x00000000\u{2502}       32 33 \u{2502}\n\
+            0x00000004\u{2502} 34 35 36 37 \u{2502}\n\
+            0x00000008\u{2502} 38          \u{2502}\n");
+        assert_eq!(dump_to_string(data, &["--offset", "100"]), "");
+    }
+
+    #[test]
+    fn dumping_with_colors() {
+        assert_eq!(dump_to_string(b"a\x00", &["--color", "--visual", "ascii"]), format!(
+            "\x1b[34m0x00000000\x1b[0m\u{2502} 61 \x1b[31m00\x1b[0m{:43}\u{2502}a\x1b[31m.\x1b[0m\n", ""));
+    }
+
+    fn reverse_to_bytes(dump: &str) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        reverse(&mut dump.as_bytes(), &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn reversing_dumps() {
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(reverse_to_bytes(&dump_to_string(&data, &[])).unwrap(), data);
+        assert_eq!(reverse_to_bytes(&dump_to_string(&data, &["--color", "--width", "7"])).unwrap(), data);
+
+        let mut expected = vec![0; 10];
+        expected.extend_from_slice(&data[10..30]);
+        assert_eq!(reverse_to_bytes(&dump_to_string(&data, &["--offset", "10", "--length", "20"])).unwrap(), expected);
+    }
+
+    #[test]
+    fn reversing_xxd_and_plain_hex() {
+        let xxd = "\
+            00000000: 3031 3233 3435 3637 3839 6162 6364 6566  0123456789abcdef\n\
+            00000010: 5859 5a                                  XYZ\n";
+        assert_eq!(reverse_to_bytes(xxd).unwrap(), b"0123456789abcdefXYZ");
+        assert_eq!(reverse_to_bytes("30313233\n34 35\n\n36").unwrap(), b"0123456");
+        assert_eq!(reverse_to_bytes("00000004: 41\n42").unwrap(), b"\0\0\0\0AB");
+        assert!(reverse_to_bytes("00000004: 41\n00000000: 42").is_err());
+        assert!(reverse_to_bytes("4?").is_err());
+    }
+
+    #[test]
+    fn reversing_lines_that_are_not_hex() {
+        let error = reverse_to_bytes("41\nhello world").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("Line 2: "));
+        assert!(reverse_to_bytes("0x41 0x42").is_err());
+        assert!(reverse_to_bytes("41 4").is_err());
+        assert!(reverse_to_bytes("00000000: 414").is_err());
+    }
+}
@@ -1,12 +1,15 @@
 use std::convert::TryFrom;
-use std::io::Result;
-use std::path::PathBuf;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
 
-use crate::byte_reader::TilingByteReader;
+use crate::byte_reader::{Snapshot, TilingByteReader};
+use crate::decompress::Compression;
+use crate::process_memory::Mapping;
+use crate::spool::SpoolProgress;
 use crate::hex_tables::*;
 use std::collections::btree_map::{BTreeMap, Range};
-use crate::hex_view_printers::TableSet;
-use crate::file_search;
+use crate::hex_view_printers::{TableSet, END_OF_FILE};
+use crate::edit_history::{Edit, EditHistory};
 
 #[derive(Copy, Clone, Debug)]
 pub enum VisualMode {
@@ -20,6 +23,11 @@ pub enum Highlight {
     Neutral,
     Positive,
     Negative,
+    Cursor,
+    Selected,
+    Edited,
+    /// The byte is in process memory that could not be read.
+    Unreadable,
 }
 
 pub trait OffsetsVisitor {
@@ -57,6 +65,10 @@ impl Highlights {
         self.highlight_width = 0;
     }
     
+    fn remove(&mut self, offset: u64) {
+        self.highlight.remove(&offset);
+    }
+
     fn insert(&mut self, offset: u64, width: u64, highlight: Highlight) {
         self.highlight.insert(offset, (width, highlight));
         if self.highlight_width < width {
@@ -64,12 +76,8 @@ impl Highlights {
         }
     }
     
-    fn iter_from(&self, offset: u64) -> Range<u64, (u64, Highlight)> {
-        let start = if offset > self.highlight_width {
-            offset - self.highlight_width
-        } else {
-            0
-        };
+    fn iter_from(&self, offset: u64) -> Range<'_, u64, (u64, Highlight)> {
+        let start = offset.saturating_sub(self.highlight_width);
         self.highlight.range(start..)
     }
 }
@@ -81,9 +89,13 @@ pub struct HexReader {
     pub group: u16,
     pub window_pos: (u64,u64),
     pub window_size: (u16,u16),
+    pub cursor: u64,
+    pub selection_anchor: Option<u64>,
     capture: Vec<u8>,
     before_image: Vec<u8>,
     highlight: Highlights,
+    matches: Vec<(u64, u64)>,
+    history: EditHistory,
     pub vis_mode: VisualMode,
 }
 
@@ -95,15 +107,23 @@ impl HexReader {
             group: 8,
             window_pos: (0,0),
             window_size: (16,32),
+            cursor: 0,
+            selection_anchor: None,
             capture: Vec::new(),
             before_image: Vec::new(),
             highlight: Highlights::new(),
+            matches: Vec::new(),
+            history: EditHistory::new(),
             vis_mode: VisualMode::Unicode
         })
     }
     
     pub fn reopen(&mut self) -> Result<()> {
-        self.reader.reopen()
+        if !self.reader.reopen()? {
+            // The file changed length, so the edits no longer apply to it.
+            self.history = EditHistory::new();
+        }
+        Ok(())
     }
     
     pub fn file_name(&self) -> &str {
@@ -117,6 +137,43 @@ impl HexReader {
     pub fn get_length(&self) -> u64 {
         self.reader.get_length()
     }
+    
+    /// Add the input that has arrived since the last call, when reading from a stream. Returns true if there was any.
+    pub fn update_length(&mut self) -> bool {
+        self.reader.update_length()
+    }
+    
+    pub fn input_progress(&self) -> Option<&SpoolProgress> {
+        self.reader.input_progress()
+    }
+    
+    pub fn is_stream(&self) -> bool {
+        self.reader.is_stream()
+    }
+    
+    pub fn is_process_memory(&self) -> bool {
+        self.reader.is_process_memory()
+    }
+    
+    pub fn compression(&self) -> Option<Compression> {
+        self.reader.compression()
+    }
+    
+    pub fn member_name(&self) -> Option<&str> {
+        self.reader.member_name()
+    }
+    
+    pub fn can_save(&self) -> bool {
+        self.reader.can_save()
+    }
+    
+    pub fn mappings(&self) -> &[Mapping] {
+        self.reader.mappings()
+    }
+    
+    pub fn address_at(&self, offset: u64) -> Option<u64> {
+        self.reader.address_at(offset)
+    }
 
     pub fn get_row_offsets_width(&self) -> usize {
         if self.reader.use_large_addresses() { 16 + 2 } else { 8 + 2 }
@@ -126,6 +183,22 @@ impl HexReader {
         self.reader.get_length() / self.line_width
     }
     
+    /// Keep the cursor and the window within the file, when it is shorter than where they were.
+    pub fn clamp_to_length(&mut self) {
+        self.cursor = self.cursor.min(self.get_length().saturating_sub(1));
+        self.window_pos.1 = self.window_pos.1.min(self.get_lines_in_file());
+    }
+    
+    /// The start offset and length of the selected bytes, if any.
+    /// The selection spans from the anchor to the cursor, including both ends.
+    pub fn selection(&self) -> Option<(u64, u64)> {
+        let anchor = self.selection_anchor?;
+        let last = self.get_length().checked_sub(1)?;
+        let start = anchor.min(self.cursor).min(last);
+        let end = anchor.max(self.cursor).min(last);
+        Some((start, end - start + 1))
+    }
+    
     pub fn capture(&mut self) -> Result<()> {
         let (x, y) = self.window_pos;
         let (w, h) = self.window_size;
@@ -146,10 +219,10 @@ impl HexReader {
         let mut line_offset = line_width * self.window_pos.1 + self.window_pos.0;
         
         let mut before_itr = self.before_image.iter();
-        let mut after_itr = self.capture.iter();
+        let after_itr = self.capture.iter();
         let mut i = 0;
         let mut begin_offset: Option<u64> = None;
-        while let Some(a) = after_itr.next() {
+        for a in after_itr {
             let offset = line_offset + i;
             
             if let Some(b) = before_itr.next() {
@@ -178,8 +251,205 @@ impl HexReader {
         self.before_image.clone_from(&self.capture);
     }
     
+    pub fn read_byte(&mut self, offset: u64) -> Result<u8> {
+        self.reader.read_byte(offset)
+    }
+    
+    /// Read up to `length` bytes from the given offset, with any edits applied.
+    pub fn read_range(&mut self, offset: u64, length: u64) -> Result<Vec<u8>> {
+        let mut buf = vec![0; usize::try_from(length).unwrap()];
+        let bytes_read = self.reader.read_at(offset, &mut buf)?;
+        buf.truncate(bytes_read);
+        Ok(buf)
+    }
+    
+    /// Fill the buffer with the bytes from the given offset, and return how many there were before the end of the file.
+    /// The bytes are taken from the captured window when possible, to avoid reading the file.
+    pub fn peek(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        if self.copy_from_capture(offset, buf) {
+            return Ok(buf.len());
+        }
+        self.reader.read_at(offset, buf)
+    }
+    
+    fn copy_from_capture(&self, offset: u64, buf: &mut [u8]) -> bool {
+        let (x, y) = self.window_pos;
+        let w = u64::from(self.window_size.0);
+        for (byte_offset, b) in (offset..).zip(buf.iter_mut()) {
+            let line = byte_offset / self.line_width;
+            let column = byte_offset % self.line_width;
+            if line < y || column < x || column >= x + w {
+                return false;
+            }
+            let index = usize::try_from((line - y) * w + column - x).unwrap();
+            match self.capture.get(index) {
+                Some(&captured) => *b = captured,
+                None => return false,
+            }
+        }
+        true
+    }
+    
+    pub fn set_byte(&mut self, offset: u64, value: u8) -> Result<()> {
+        self.edit(offset, 1, vec![value])
+    }
+    
+    /// Overwrite the bytes at the offset, as a single edit.
+    pub fn replace_bytes(&mut self, offset: u64, bytes: &[u8]) -> Result<()> {
+        self.edit(offset, u64::try_from(bytes.len()).unwrap(), bytes.to_vec())
+    }
+    
+    pub fn insert_byte(&mut self, offset: u64, value: u8) -> Result<()> {
+        self.edit(offset, 0, vec![value])
+    }
+    
+    pub fn delete_byte(&mut self, offset: u64) -> Result<()> {
+        self.edit(offset, 1, Vec::new())
+    }
+    
+    /// Overwrite the bytes at each of the offsets, as a single edit that is undone at once.
+    pub fn replace_bytes_at(&mut self, offsets: &[u64], bytes: &[u8]) -> Result<()> {
+        let length = u64::try_from(bytes.len()).unwrap();
+        let mut edits = Vec::with_capacity(offsets.len());
+        for &offset in offsets {
+            match self.apply_new_edit(offset, length, bytes.to_vec()) {
+                Ok(edit) => edits.push(edit),
+                Err(error) => {
+                    // Keep the replacements that were made, so they can be undone.
+                    self.history.record_group(edits);
+                    return Err(error);
+                }
+            }
+        }
+        self.history.record_group(edits);
+        Ok(())
+    }
+    
+    fn edit(&mut self, offset: u64, remove_length: u64, new: Vec<u8>) -> Result<()> {
+        let edit = self.apply_new_edit(offset, remove_length, new)?;
+        self.history.record(edit);
+        Ok(())
+    }
+    
+    /// Make an edit that is not recorded in the history yet.
+    fn apply_new_edit(&mut self, offset: u64, remove_length: u64, new: Vec<u8>) -> Result<Edit> {
+        if self.reader.is_process_memory() && remove_length != u64::try_from(new.len()).unwrap() {
+            // The offsets of the bytes must stay the same, so they keep matching their addresses.
+            return Err(Error::new(ErrorKind::InvalidInput, "Bytes cannot be inserted into or deleted from process memory."));
+        }
+        let mut old = vec![0; usize::try_from(remove_length).unwrap()];
+        let bytes_read = self.reader.read_at(offset, &mut old)?;
+        if bytes_read < old.len() {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        self.reader.replace(offset, remove_length, &new);
+        Ok(Edit::new(offset, old, new))
+    }
+    
+    /// Change the byte that was written or inserted by the most recent edit, without recording a new edit.
+    pub fn amend_byte(&mut self, offset: u64, value: u8) {
+        self.reader.replace(offset, 1, &[value]);
+        self.history.amend_last(vec![value]);
+    }
+    
+    /// Revert the most recent edit, and return the offset it was made at.
+    pub fn undo(&mut self) -> Option<u64> {
+        let edits = self.history.undo()?;
+        for edit in edits.iter().rev() {
+            self.apply(&edit.inverse());
+        }
+        Some(edits[0].offset)
+    }
+    
+    /// Re-apply the most recently undone edit, and return the offset it was made at.
+    pub fn redo(&mut self) -> Option<u64> {
+        let edits = self.history.redo()?;
+        for edit in &edits {
+            self.apply(edit);
+        }
+        Some(edits[0].offset)
+    }
+    
+    fn apply(&mut self, edit: &Edit) {
+        let remove_length = u64::try_from(edit.old.len()).unwrap();
+        self.reader.replace(edit.offset, remove_length, &edit.new);
+    }
+    
+    pub fn undo_count(&self) -> usize {
+        self.history.undo_count()
+    }
+    
+    pub fn redo_count(&self) -> usize {
+        self.history.redo_count()
+    }
+    
+    pub fn has_changes(&self) -> bool {
+        self.history.is_modified()
+    }
+    
+    pub fn save(&mut self) -> Result<()> {
+        self.reader.save()?;
+        self.history.mark_saved();
+        Ok(())
+    }
+    
+    pub fn save_copy(&mut self, path: &Path) -> Result<()> {
+        self.reader.save_copy(path)
+    }
+    
     pub fn clear_highlights(&mut self) {
         self.highlight.clear();
+        self.matches.clear();
+    }
+    
+    /// Record a search match. Matches must be added in the order of their offsets.
+    pub fn add_match(&mut self, offset: u64, length: u64) {
+        self.matches.push((offset, length));
+        self.highlight(offset, length, Highlight::Positive);
+    }
+    
+    pub fn matches(&self) -> &[(u64, u64)] {
+        &self.matches
+    }
+
+    /// Forget the matches that overlap the given range, because their bytes were changed.
+    pub fn remove_matches_overlapping(&mut self, offset: u64, length: u64) {
+        let highlight = &mut self.highlight;
+        self.matches.retain(|&(start, match_length)| {
+            let overlaps = start < offset + length && offset < start + match_length;
+            if overlaps {
+                highlight.remove(start);
+            }
+            !overlaps
+        });
+    }
+
+    /// The first match that starts at or after the offset, wrapping around to the first match.
+    pub fn match_at_or_after(&self, offset: u64) -> Option<(u64, u64)> {
+        let index = self.matches.partition_point(|&(start, _)| start < offset);
+        self.matches.get(index).or_else(|| self.matches.first()).copied()
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+    
+    /// The index of the match that starts at the given offset, if any.
+    pub fn match_index_at(&self, offset: u64) -> Option<usize> {
+        self.matches.binary_search_by_key(&offset, |&(start, _)| start).ok()
+    }
+    
+    /// The offset of the first match after the given offset, wrapping around to the first match in the file.
+    pub fn next_match(&self, offset: u64) -> Option<u64> {
+        let index = self.matches.partition_point(|&(start, _)| start <= offset);
+        self.matches.get(index).or_else(|| self.matches.first()).map(|&(start, _)| start)
+    }
+    
+    /// The offset of the last match before the given offset, wrapping around to the last match in the file.
+    pub fn previous_match(&self, offset: u64) -> Option<u64> {
+        let index = self.matches.partition_point(|&(start, _)| start < offset);
+        let previous = if index > 0 { self.matches.get(index - 1) } else { None };
+        previous.or_else(|| self.matches.last()).map(|&(start, _)| start)
     }
     
     pub fn highlight(&mut self, offset: u64, width: u64, highlight: Highlight) {
@@ -196,15 +466,17 @@ impl HexReader {
         }
         let height = u64::try_from(h.min(capture_height)).unwrap();
         
+        // Process memory is shown at its virtual addresses, rather than at its offsets.
+        let address = |offset: u64| self.reader.address_at(offset).unwrap_or(offset);
         if self.reader.use_large_addresses() {
             for i in 0..height {
                 let offset = base_offset + i * self.line_width;
-                visitor.offset(&format!("0x{:016X}", offset));
+                visitor.offset(&format!("0x{:016X}", address(offset)));
             }
         } else {
             for i in 0..height {
                 let offset = base_offset + i * self.line_width;
-                visitor.offset(&format!("0x{:08X}", offset));
+                visitor.offset(&format!("0x{:08X}", address(offset)));
             }
         }
         visitor.end();
@@ -221,6 +493,12 @@ impl HexReader {
         let mut hl_iter = self.highlight.iter_from(line_offset);
         let mut hl = hl_iter.next();
 
+        let selection = self.selection();
+        let is_selected = |offset: u64| match selection {
+            Some((start, length)) => start <= offset && offset < start + length,
+            None => false,
+        };
+
         let mut i = 0;
         let mut highlight = Highlight::Neutral;
         let mut hl_end = 0;
@@ -241,7 +519,17 @@ impl HexReader {
             }
 
             let r = usize::from(*b);
-            visitor.byte(r, highlight);
+            if offset == self.cursor {
+                visitor.byte(r, Highlight::Cursor);
+            } else if is_selected(offset) {
+                visitor.byte(r, Highlight::Selected);
+            } else if self.reader.is_changed(offset) {
+                visitor.byte(r, Highlight::Edited);
+            } else if self.reader.is_unreadable(offset) {
+                visitor.byte(0, Highlight::Unreadable);
+            } else {
+                visitor.byte(r, highlight);
+            }
 
             i += 1;
             if i == hl_end {
@@ -257,10 +545,17 @@ impl HexReader {
             }
         }
 
+        // While inserting, the cursor can be just past the last byte, where bytes are appended.
+        let window_start = line_width * self.window_pos.1 + self.window_pos.0;
+        let row = (line_offset - window_start) / line_width;
+        if self.cursor == line_offset + i && self.cursor == self.get_length() && row < u64::from(self.window_size.1) {
+            visitor.byte(END_OF_FILE, Highlight::Cursor);
+        }
+
         visitor.end();
     }
     
-    fn vis_table(&self) -> &'static [&'static str; 256] {
+    pub fn vis_table(&self) -> &'static [&'static str; 256] {
         match self.vis_mode {
             VisualMode::Unicode => UNICODE_TEXT_TABLE,
             VisualMode::Ascii => ASCII_TEXT_TABLE,
@@ -272,6 +567,8 @@ impl HexReader {
         for i in 0..BYTE_RENDER.len() {
             table_set.push_byte(&BYTE_CATEGORY[i], BYTE_RENDER[i]);
         }
+        table_set.push_unreadable("??");
+        table_set.push_end_of_file("  ");
     }
 
     pub fn generate_visual_tables(&self, table_set: &mut TableSet) {
@@ -279,6 +576,11 @@ impl HexReader {
         for i in 0..BYTE_RENDER.len() {
             table_set.push_byte(&BYTE_CATEGORY[i], table[i]);
         }
+        table_set.push_unreadable(match self.vis_mode {
+            VisualMode::Unicode => "\u{2591}",
+            _ => "?",
+        });
+        table_set.push_end_of_file(" ");
     }
     
     pub fn set_visual_mode(&mut self, mode: VisualMode) {
@@ -289,12 +591,8 @@ impl HexReader {
         &self.vis_mode
     }
     
-    pub fn search(&mut self, bytes: &[u8]) {
-        let file = self.reader.open_file().unwrap();
-        let len = u64::try_from(bytes.len()).unwrap();
-        file_search::search(file, bytes, |start| {
-            self.highlight(start, len, Highlight::Positive);
-        });
+    pub fn snapshot(&mut self) -> Result<Snapshot> {
+        self.reader.snapshot()
     }
 }
 
@@ -302,8 +600,6 @@ impl HexReader {
 mod tests {
     use std::io::Write;
 
-    use tempfile;
-
     use super::*;
 
     impl OffsetsVisitor for String {
@@ -319,10 +615,13 @@ mod tests {
     
     impl HexVisitor for String {
         fn byte(&mut self, index: usize, highlight: Highlight) {
-            self.push_str(BYTE_RENDER[index]);
+            self.push_str(BYTE_RENDER.get(index).unwrap_or(&"__"));
             match highlight {
                 Highlight::Positive => self.push( '+' ),
                 Highlight::Negative => self.push( '-' ),
+                Highlight::Cursor => self.push( '#' ),
+                Highlight::Selected => self.push( '~' ),
+                Highlight::Edited => self.push( '*' ),
                 _ => (),
             }
             self.push(' ');
@@ -335,7 +634,7 @@ mod tests {
         fn next_line(&mut self) {
             let ch = self.pop();
             if let Some(c) = ch {
-                if c == '+' || c == '-' {
+                if c == '+' || c == '-' || c == '#' || c == '~' || c == '*' {
                     self.push(c);
                 }
             }
@@ -350,7 +649,7 @@ mod tests {
     #[test]
     fn getting_hex_of_file_top_left_window() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
         
         let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
         reader.window_pos = (0,0);
@@ -362,13 +661,13 @@ mod tests {
         // Bytes:  Hex:
         //  01      30 31
         //  45      34 35
-        assert_eq!(hex, "30 31\n34 35")
+        assert_eq!(hex, "30# 31\n34 35")
     }
     
     #[test]
     fn getting_hex_of_file_with_highlight() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
         
         let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
         reader.highlight(1, 1, Highlight::Positive);
@@ -384,13 +683,13 @@ mod tests {
         // Bytes:  Hex:
         //  01      30 31
         //  45      34 35
-        assert_eq!(hex, "30 31+\n34- 35-")
+        assert_eq!(hex, "30# 31+\n34- 35-")
     }
     
     #[test]
     fn highlight_to_left_of_window_must_not_bleed_in() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
         
         let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
         reader.highlight(0, 2, Highlight::Positive);
@@ -403,10 +702,157 @@ mod tests {
         assert_eq!(hex, "32 33\n36 37")
     }
     
+    #[test]
+    fn cursor_is_drawn_on_top_of_highlights() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        reader.highlight(4, 2, Highlight::Positive);
+        reader.cursor = 5;
+        reader.window_pos = (0,0);
+        reader.window_size = (2,2);
+        reader.line_width = 4;
+        reader.capture().unwrap();
+        let mut hex = String::new();
+        reader.visit_hex(&mut hex);
+        assert_eq!(hex, "30 31\n34+ 35#")
+    }
+    
+    #[test]
+    fn selected_bytes_are_highlighted() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        reader.selection_anchor = Some(6);
+        reader.cursor = 1;
+        reader.window_pos = (0,0);
+        reader.window_size = (4,2);
+        reader.line_width = 4;
+        reader.capture().unwrap();
+        assert_eq!(reader.selection(), Some((1, 6)));
+        let mut hex = String::new();
+        reader.visit_hex(&mut hex);
+        assert_eq!(hex, "30 31# 32~ 33~\n34~ 35~ 36~ 37")
+    }
+    
+    #[test]
+    fn peeking_outside_the_captured_window() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        reader.window_pos = (1,1);
+        reader.window_size = (2,2);
+        reader.line_width = 4;
+        reader.capture().unwrap();
+        
+        let mut buf = [0; 2];
+        assert!(reader.copy_from_capture(9, &mut buf));
+        assert_eq!(&buf, b"9a");
+        assert!(!reader.copy_from_capture(10, &mut buf));
+        assert_eq!(reader.peek(10, &mut buf).unwrap(), 2);
+        assert_eq!(&buf, b"ab");
+        let mut buf = [0; 4];
+        assert_eq!(reader.peek(14, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ef");
+    }
+    
+    #[test]
+    fn navigating_between_matches() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"abc abc abc").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        assert_eq!(reader.next_match(0), None);
+        for &offset in &[0, 4, 8] {
+            reader.add_match(offset, 3);
+        }
+        assert_eq!(reader.match_count(), 3);
+        assert_eq!(reader.match_index_at(4), Some(1));
+        assert_eq!(reader.match_index_at(5), None);
+        assert_eq!(reader.next_match(0), Some(4));
+        assert_eq!(reader.next_match(5), Some(8));
+        assert_eq!(reader.next_match(8), Some(0));
+        assert_eq!(reader.previous_match(8), Some(4));
+        assert_eq!(reader.previous_match(5), Some(4));
+        assert_eq!(reader.previous_match(0), Some(8));
+        reader.clear_highlights();
+        assert_eq!(reader.match_count(), 0);
+    }
+    
+    #[test]
+    fn edited_bytes_are_highlighted() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        reader.set_byte(0, 0xFF).unwrap();
+        reader.set_byte(5, 0xAA).unwrap();
+        reader.cursor = 4;
+        reader.window_pos = (0,0);
+        reader.window_size = (2,2);
+        reader.line_width = 4;
+        reader.capture().unwrap();
+        let mut hex = String::new();
+        reader.visit_hex(&mut hex);
+        assert_eq!(hex, "ff* 31\n34# aa*")
+    }
+    
+    #[test]
+    fn undo_and_redo_edits() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        reader.set_byte(1, b'a').unwrap();
+        reader.set_byte(2, b'b').unwrap();
+        reader.amend_byte(2, b'c');
+        assert!(reader.has_changes());
+        assert_eq!(reader.read_byte(2).unwrap(), b'c');
+        
+        assert_eq!(reader.undo(), Some(2));
+        assert_eq!(reader.read_byte(2).unwrap(), b'2');
+        assert_eq!(reader.undo(), Some(1));
+        assert_eq!(reader.read_byte(1).unwrap(), b'1');
+        assert_eq!(reader.undo(), None);
+        assert!(!reader.has_changes());
+        
+        assert_eq!(reader.redo(), Some(1));
+        assert_eq!(reader.read_byte(1).unwrap(), b'a');
+        assert_eq!(reader.undo_count(), 1);
+        assert_eq!(reader.redo_count(), 1);
+    }
+    
+    #[test]
+    fn undo_and_redo_inserts_and_deletes() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123").unwrap();
+
+        let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
+        reader.insert_byte(1, b'a').unwrap();
+        reader.delete_byte(3).unwrap();
+        assert_eq!(reader.get_length(), 4);
+        let mut buf = [0; 4];
+        reader.reader.read_at(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"0a13");
+        
+        assert_eq!(reader.undo(), Some(3));
+        assert_eq!(reader.undo(), Some(1));
+        assert_eq!(reader.get_length(), 4);
+        reader.reader.read_at(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"0123");
+        
+        assert_eq!(reader.redo(), Some(1));
+        assert_eq!(reader.get_length(), 5);
+        assert!(reader.delete_byte(5).is_err());
+    }
+    
     #[test]
     fn hex_view_bigger_than_file() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
 
         let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
         reader.window_pos = (0,0);
@@ -420,7 +866,7 @@ mod tests {
         //  4567    34 35 36 37
         //  89ab    38 39 61 62
         //  cdef    63 64 65 66
-        assert_eq!(hex, "30 31 32 33\n34 35 36 37\n38 39 61 62\n63 64 65 66");
+        assert_eq!(hex, "30# 31 32 33\n34 35 36 37\n38 39 61 62\n63 64 65 66");
         let mut offsets = String::new();
         reader.visit_row_offsets(&mut offsets);
         assert_eq!(offsets, "0x00000000\n0x00000004\n0x00000008\n0x0000000C");
@@ -429,7 +875,7 @@ mod tests {
     #[test]
     fn hex_view_bigger_than_unaligned_file() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcde").unwrap();
+        tmpf.write_all(b"0123456789abcde").unwrap();
 
         let mut reader = HexReader::new(TilingByteReader::new(tmpf.path()).unwrap()).unwrap();
         reader.window_pos = (0,0);
@@ -443,9 +889,27 @@ mod tests {
         //  4567    34 35 36 37
         //  89ab    38 39 61 62
         //  cdef    63 64 65 66
-        assert_eq!(hex, "30 31 32 33\n34 35 36 37\n38 39 61 62\n63 64 65");
+        assert_eq!(hex, "30# 31 32 33\n34 35 36 37\n38 39 61 62\n63 64 65");
         let mut offsets = String::new();
         reader.visit_row_offsets(&mut offsets);
         assert_eq!(offsets, "0x00000000\n0x00000004\n0x00000008\n0x0000000C");
     }
+    
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn viewing_process_memory_at_its_addresses() {
+        let mut reader = HexReader::new(TilingByteReader::from_process(std::process::id()).unwrap()).unwrap();
+        let first = reader.mappings()[0].clone();
+        reader.window_pos = (0,0);
+        reader.window_size = (4,2);
+        reader.line_width = 4;
+        reader.capture().unwrap();
+        let mut offsets = String::new();
+        reader.visit_row_offsets(&mut offsets);
+        assert_eq!(offsets, format!("0x{:016X}\n0x{:016X}", first.start, first.start + 4));
+        
+        assert!(reader.insert_byte(0, 0).is_err());
+        assert!(reader.delete_byte(0).is_err());
+        assert!(!reader.can_save());
+    }
 }
@@ -1,53 +1,122 @@
 use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 use cursive::align::HAlign;
-use cursive::event::{Event, Key, MouseEvent};
+use cursive::event::{Event, Key, MouseButton, MouseEvent};
 use cursive::event::EventResult;
 use cursive::Printer;
 use cursive::theme::ColorStyle;
 use cursive::traits::View;
-use cursive::Vec2;
-use unicode_width::UnicodeWidthStr;
-
-use crate::hex_reader::{HexReader, VisualMode, Highlight};
+use cursive::{CbSink, Vec2};
+use cursive::views::TextContent;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::background_search::{spawn_search, SearchHandle, SearchResults};
+use crate::byte_reader::Snapshot;
+use crate::data_inspector::{inspect, INSPECT_LENGTH};
+use crate::decompress::Compression;
+use crate::hex_reader::{HexReader, VisualMode};
+use crate::process_memory::Mapping;
+use crate::search_matcher::Matcher;
+use crate::spool::SpoolProgress;
 use crate::xxv_state::ReaderState;
 use crate::hex_view_printers::{OffsetPrinter, HexPrinter, VisualPrinter, TableSet};
 
+/// The width of the data inspector panel, not counting the line that separates it from the visual column.
+const INSPECTOR_WIDTH: usize = 34;
+const INSPECTOR_LABEL_WIDTH: usize = 10;
+/// The width the hex and visual columns keep when the inspector is shown, which fits 8 bytes per line.
+const MIN_HEX_COLUMN_WIDTH: usize = 34;
+/// How far past the cursor a search preview looks for a match to move the cursor to.
+const PREVIEW_SEARCH_LENGTH: u64 = 1024 * 1024;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum EditColumn {
+    Hex,
+    Visual,
+}
+
 pub struct HexView {
     reader: HexReader,
+    edit_column: Option<EditColumn>,
+    insert_mode: bool,
+    half_byte_written: bool,
     invalidated_resize: bool,
     invalidated_data_changed: bool,
     show_visual_view: bool,
+    show_inspector: bool,
     offsets_column_pos: Vec2,
     offsets_column_size: Vec2,
     hex_column_pos: Vec2,
     hex_column_size: Vec2,
     visual_column_pos: Vec2,
     visual_column_size: Vec2,
+    inspector_column_pos: Vec2,
+    inspector_column_size: Vec2,
+    inspector_rows: Vec<(&'static str, String)>,
+    inspector_page: usize,
     hex_tables: TableSet,
     visual_tables: TableSet,
+    status: TextContent,
+    message: Option<String>,
+    search: Option<RunningSearch>,
+    search_generation: u64,
+    search_origin: Option<u64>,
+    /// Where the cursor was before a search preview moved it.
+    preview_origin: Option<u64>,
+    /// The bytes to replace the matches with, once the running search is done.
+    pending_replacement: Option<Vec<u8>>,
+}
+
+struct RunningSearch {
+    handle: SearchHandle,
+    searched: u64,
+    length: u64,
 }
 
 impl HexView {
     pub fn new(reader: HexReader) -> HexView {
         HexView {
             reader,
+            edit_column: None,
+            insert_mode: false,
+            half_byte_written: false,
             invalidated_resize: true,
             invalidated_data_changed: true,
             show_visual_view: true,
+            show_inspector: false,
             offsets_column_pos: Vec2::new(0, 0),
             offsets_column_size: Vec2::new(0, 0),
             hex_column_pos: Vec2::new(0, 0),
             hex_column_size: Vec2::new(0, 0),
             visual_column_pos: Vec2::new(0, 0),
             visual_column_size: Vec2::new(0, 0),
+            inspector_column_pos: Vec2::new(0, 0),
+            inspector_column_size: Vec2::new(0, 0),
+            inspector_rows: Vec::new(),
+            inspector_page: 0,
             hex_tables: TableSet::new(),
             visual_tables: TableSet::new(),
+            status: TextContent::new(""),
+            message: None,
+            search: None,
+            search_generation: 0,
+            search_origin: None,
+            preview_origin: None,
+            pending_replacement: None,
         }
     }
     
     pub fn switch_reader(&mut self, reader: HexReader) {
+        self.cancel_search();
+        // Results from the search in the previous file must not be shown in the new file.
+        self.search_generation += 1;
+        self.preview_origin = None;
         self.reader = reader;
+        self.edit_column = None;
+        self.half_byte_written = false;
         self.invalidated_data_changed = true;
         self.invalidated_resize = true;
     }
@@ -56,9 +125,11 @@ impl HexView {
         ReaderState::new(&self.reader)
     }
     
+    pub fn status_content(&self) -> TextContent {
+        self.status.clone()
+    }
+    
     pub fn go_to_offset(&mut self, offset: u64) {
-        self.reader.clear_highlights();
-
         let current_pos = self.reader.window_pos;
         let window_size = self.reader.window_size;
         let current_size = (u64::from(window_size.0), u64::from(window_size.1));
@@ -69,11 +140,11 @@ impl HexView {
         let lines_in_file = self.reader.get_lines_in_file();
         
         let target_pos = if line <= lines_in_file {
-            self.reader.highlight(offset, 1, Highlight::Positive);
             (line_offset, line)
         } else {
             (0, lines_in_file)
         };
+        self.reader.cursor = self.clamp_to_file(offset);
         
         // Only adjust the window position if the target position is not within the window bounds.
         if target_pos.0 < current_pos.0 || target_pos.1 < current_pos.1 ||
@@ -93,6 +164,7 @@ impl HexView {
         if self.reader.window_pos.1 > lines_in_file {
             self.reader.window_pos.1 = lines_in_file;
         }
+        self.scroll_to_cursor();
         self.invalidated_resize = true;
         self.invalidated_data_changed = true;
     }
@@ -115,9 +187,404 @@ impl HexView {
         self.reader.get_length()
     }
     
-    pub fn search(&mut self, bytes: &[u8]) {
+    /// Show the input that has arrived since the last update, when reading from a stream.
+    pub fn update_input(&mut self) {
+        if self.reader.update_length() {
+            self.invalidated_data_changed = true;
+        }
+        let finished = self.reader.input_progress().filter(|p| p.is_finished()).cloned();
+        if finished.is_some() {
+            // The saved position of a stream could only be checked against its length once all of it arrived.
+            self.reader.clamp_to_length();
+        }
+        let error = finished.and_then(|p| p.error());
+        match error {
+            Some(error) if self.reader.compression().is_some() => self.show_message(format!("Decompressing failed: {}", error)),
+            Some(error) if self.reader.member_name().is_some() => self.show_message(format!("Extracting failed: {}", error)),
+            Some(error) => self.show_message(format!("Reading the input failed: {}", error)),
+            None => self.update_status(),
+        }
+    }
+    
+    /// The progress of reading the input, if reading from a stream.
+    pub fn input_progress(&self) -> Option<SpoolProgress> {
+        self.reader.input_progress().cloned()
+    }
+    
+    pub fn is_process_memory(&self) -> bool {
+        self.reader.is_process_memory()
+    }
+    
+    /// The format of the compressed file, when showing its decompressed contents.
+    pub fn compression(&self) -> Option<Compression> {
+        self.reader.compression()
+    }
+    
+    /// The name of the archive member, when showing a member that was extracted from an archive.
+    pub fn member_name(&self) -> Option<String> {
+        self.reader.member_name().map(|name| name.to_string())
+    }
+    
+    /// Take a snapshot of the edited contents, that can be read from another thread.
+    pub fn snapshot(&mut self) -> Result<Snapshot> {
+        self.reader.snapshot()
+    }
+    
+    /// Select the range of bytes, and move the cursor to its start.
+    pub fn select_range(&mut self, offset: u64, length: u64) {
+        self.go_to_offset(offset);
+        if length > 0 {
+            self.reader.selection_anchor = Some(self.clamp_to_file(offset + length - 1));
+        }
+    }
+    
+    /// Whether edits can be saved to where the bytes were read from, rather than only to a copy.
+    pub fn can_save(&self) -> bool {
+        self.reader.can_save()
+    }
+    
+    pub fn mappings(&self) -> Vec<Mapping> {
+        self.reader.mappings().to_vec()
+    }
+    
+    /// Start searching the range of offsets in the background, replacing the results of any previous search.
+    pub fn search(&mut self, matcher: Box<dyn Matcher>, range: Range<u64>, sink: CbSink) -> Result<()> {
+        self.cancel_search();
+        self.reader.clear_highlights();
+        self.search_generation += 1;
+        let snapshot = self.reader.snapshot()?;
+        let end = range.end.min(snapshot.len());
+        let length = end - range.start.min(end);
+        let handle = spawn_search(snapshot, matcher, range, self.search_generation, sink);
+        self.search = Some(RunningSearch { handle, searched: 0, length });
+        self.search_origin = Some(self.reader.cursor);
+        self.preview_origin = None;
+        Ok(())
+    }
+    
+    /// Highlight the matches in the viewport, and move the cursor to the first match after where it was
+    /// before the preview started, if there is one nearby. Used while a query is being typed.
+    pub fn preview_search(&mut self, matcher: &dyn Matcher) -> Result<()> {
+        self.cancel_search();
+        self.search_generation += 1;
         self.reader.clear_highlights();
-        self.reader.search(bytes);
+        let origin = *self.preview_origin.get_or_insert(self.reader.cursor);
+        let overlap = u64::try_from(matcher.max_match_length()).unwrap().saturating_sub(1);
+        
+        let ahead = self.reader.read_range(origin, PREVIEW_SEARCH_LENGTH + overlap)?;
+        match matcher.find_at(&ahead, 0) {
+            Some((start, _)) => self.go_to_match(origin + u64::try_from(start).unwrap()),
+            None => self.go_to_match(origin),
+        }
+        
+        let line_width = self.reader.line_width;
+        let start = self.reader.window_pos.1 * line_width;
+        let length = usize::try_from(u64::from(self.reader.window_size.1) * line_width).unwrap();
+        let bytes = self.reader.read_range(start, u64::try_from(length).unwrap() + overlap)?;
+        let mut pos = 0;
+        while let Some((found, found_length)) = matcher.find_at(&bytes, pos) {
+            if found >= length {
+                break;
+            }
+            self.reader.add_match(start + u64::try_from(found).unwrap(), u64::try_from(found_length).unwrap());
+            pos = matcher.next_start(found, found_length);
+        }
+        self.invalidated_data_changed = true;
+        Ok(())
+    }
+    
+    /// Remove the highlights of the search preview, and move the cursor back to where it was before the preview.
+    pub fn end_preview(&mut self) {
+        if let Some(origin) = self.preview_origin.take() {
+            self.reader.clear_highlights();
+            self.go_to_match(origin);
+            self.invalidated_data_changed = true;
+        }
+    }
+    
+    pub fn add_search_results(&mut self, results: SearchResults) {
+        if results.generation != self.search_generation {
+            return;
+        }
+        for (offset, length) in results.matches {
+            self.reader.add_match(offset, length);
+            if self.search_origin.is_some_and(|origin| offset >= origin) {
+                // Move to the first match after where the cursor was when the search started.
+                self.search_origin = None;
+                self.go_to_match(offset);
+            }
+        }
+        if let Some(search) = &mut self.search {
+            search.searched = results.searched;
+        }
+        if results.finished {
+            self.search = None;
+            if self.search_origin.take().is_some() {
+                // There were no matches after the cursor, so wrap around to the first match.
+                self.next_match();
+            }
+            if let Some(error) = results.error {
+                self.show_message(format!("Search failed: {}", error));
+            }
+        }
+    }
+    
+    fn cancel_search(&mut self) -> EventResult {
+        self.pending_replacement = None;
+        if let Some(search) = self.search.take() {
+            search.handle.cancel();
+            self.search_origin = None;
+            self.show_message("Search cancelled".to_string());
+        }
+        EventResult::Consumed(None)
+    }
+    
+    /// The selected bytes, or the bytes in the visible lines if nothing is selected, as an offset and a length.
+    pub fn selection_or_viewport(&self) -> (u64, u64) {
+        if let Some(selection) = self.reader.selection() {
+            return selection;
+        }
+        let line_width = self.reader.line_width;
+        let start = self.reader.window_pos.1 * line_width;
+        let end = start + u64::from(self.reader.window_size.1) * line_width;
+        let length = self.reader.get_length();
+        (start.min(length), end.min(length) - start.min(length))
+    }
+    
+    pub fn read_range(&mut self, offset: u64, length: u64) -> Result<Vec<u8>> {
+        self.reader.read_range(offset, length)
+    }
+    
+    /// Offer to replace the matches of the running search with the given bytes, when the search is done.
+    pub fn set_pending_replacement(&mut self, bytes: Vec<u8>) {
+        self.pending_replacement = Some(bytes);
+    }
+    
+    /// The replacement for the matches of the search, if it has just finished.
+    pub fn take_pending_replacement(&mut self) -> Option<Vec<u8>> {
+        if self.search.is_some() {
+            return None;
+        }
+        self.pending_replacement.take()
+    }
+    
+    /// Replace the match at the cursor, or the next match after it, and move the cursor to the following match.
+    /// Returns the offset of the replaced match, or None if there are no matches left.
+    pub fn replace_next_match(&mut self, bytes: &[u8]) -> Result<Option<u64>> {
+        let (offset, length) = match self.reader.match_at_or_after(self.reader.cursor) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        if length != u64::try_from(bytes.len()).unwrap() {
+            let message = format!("The match at 0x{:X} is {} bytes long, but the replacement is {} bytes long.",
+                offset, length, bytes.len());
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        self.reader.replace_bytes(offset, bytes)?;
+        self.reader.remove_matches_overlapping(offset, length);
+        match self.reader.match_at_or_after(offset + length) {
+            Some((next, _)) => self.go_to_match(next),
+            None => self.go_to_match(offset),
+        }
+        self.invalidated_data_changed = true;
+        Ok(Some(offset))
+    }
+    
+    /// Replace every match that is as long as the replacement, and does not overlap an earlier replaced match.
+    /// Returns the number of replaced and skipped matches.
+    pub fn replace_all_matches(&mut self, bytes: &[u8]) -> Result<(usize, usize)> {
+        let length = u64::try_from(bytes.len()).unwrap();
+        let mut offsets = Vec::new();
+        let mut skipped = 0;
+        let mut replaced_until = 0;
+        for &(offset, match_length) in self.reader.matches() {
+            if match_length != length || (!offsets.is_empty() && offset < replaced_until) {
+                skipped += 1;
+                continue;
+            }
+            offsets.push(offset);
+            replaced_until = offset + length;
+        }
+        // All matches are replaced in one edit, which is undone at once.
+        let result = self.reader.replace_bytes_at(&offsets, bytes);
+        // The bytes of the matches have changed, so they no longer match.
+        self.reader.clear_highlights();
+        self.invalidated_data_changed = true;
+        result?;
+        Ok((offsets.len(), skipped))
+    }
+    
+    pub fn save_copy(&mut self, path: &Path) -> Result<()> {
+        self.reader.save_copy(path)
+    }
+    
+    pub fn file_path(&self) -> PathBuf {
+        self.reader.get_path()
+    }
+    
+    pub fn file_name(&self) -> &str {
+        self.reader.file_name()
+    }
+    
+    /// The offset and length of every match of the current search, in offset order.
+    pub fn search_matches(&self) -> Vec<(u64, u64)> {
+        self.reader.matches().to_vec()
+    }
+    
+    pub fn visual_table(&self) -> &'static [&'static str; 256] {
+        self.reader.vis_table()
+    }
+    
+    /// Show a message in the status bar, until the next event.
+    pub fn show_message(&mut self, message: String) {
+        self.message = Some(message);
+        self.update_status();
+    }
+    
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.reader.has_changes()
+    }
+    
+    pub fn save(&mut self) -> Result<()> {
+        self.reader.save()?;
+        // The next digit starts a new byte, rather than changing the byte that was saved.
+        self.half_byte_written = false;
+        self.invalidated_data_changed = true;
+        Ok(())
+    }
+    
+    fn start_editing(&mut self) -> EventResult {
+        self.edit_column = Some(EditColumn::Hex);
+        self.half_byte_written = false;
+        EventResult::Consumed(None)
+    }
+    
+    fn stop_editing(&mut self) -> EventResult {
+        self.edit_column = None;
+        self.half_byte_written = false;
+        self.move_cursor(self.reader.cursor);
+        EventResult::Consumed(None)
+    }
+    
+    fn switch_edit_column(&mut self) -> EventResult {
+        self.edit_column = match self.edit_column {
+            Some(EditColumn::Hex) if self.show_visual_view => Some(EditColumn::Visual),
+            Some(_) => Some(EditColumn::Hex),
+            None => None,
+        };
+        self.half_byte_written = false;
+        EventResult::Consumed(None)
+    }
+    
+    fn toggle_insert_mode(&mut self) -> EventResult {
+        self.insert_mode = !self.insert_mode;
+        self.half_byte_written = false;
+        self.move_cursor(self.reader.cursor);
+        EventResult::Consumed(None)
+    }
+    
+    fn on_edit_char(&mut self, column: EditColumn, c: char) -> EventResult {
+        let cursor = self.reader.cursor;
+        let length = self.reader.get_length();
+        // Past the last byte, there is nothing to overwrite, but bytes can be appended.
+        if cursor >= length && !self.insert_mode {
+            return EventResult::Consumed(None);
+        }
+        match column {
+            EditColumn::Hex => {
+                if let Some(digit) = c.to_digit(16) {
+                    let digit = u8::try_from(digit).unwrap();
+                    if self.half_byte_written {
+                        let old = self.reader.read_byte(cursor).unwrap_or(0);
+                        self.reader.amend_byte(cursor, (old & 0xF0) | digit);
+                        self.half_byte_written = false;
+                        self.advance_cursor();
+                    } else if self.insert_mode {
+                        self.half_byte_written = self.reader.insert_byte(cursor, digit << 4).is_ok();
+                    } else {
+                        let old = self.reader.read_byte(cursor).unwrap_or(0);
+                        self.half_byte_written = self.reader.set_byte(cursor, (digit << 4) | (old & 0x0F)).is_ok();
+                    }
+                }
+            },
+            EditColumn::Visual => {
+                if c.is_ascii() {
+                    let byte = u8::try_from(u32::from(c)).unwrap();
+                    let result = if self.insert_mode {
+                        self.reader.insert_byte(cursor, byte)
+                    } else {
+                        self.reader.set_byte(cursor, byte)
+                    };
+                    if result.is_ok() {
+                        self.advance_cursor();
+                    }
+                }
+            },
+        }
+        self.invalidated_data_changed = true;
+        // Characters are consumed even if they cannot be written, so they don't trigger commands.
+        EventResult::Consumed(None)
+    }
+    
+    fn delete_at_cursor(&mut self) -> EventResult {
+        if self.reader.delete_byte(self.reader.cursor).is_ok() {
+            self.move_cursor(self.reader.cursor);
+            self.invalidated_data_changed = true;
+        }
+        EventResult::Consumed(None)
+    }
+    
+    fn delete_before_cursor(&mut self) -> EventResult {
+        let cursor = self.reader.cursor;
+        if cursor > 0 && self.reader.delete_byte(cursor - 1).is_ok() {
+            self.move_cursor(cursor - 1);
+            self.invalidated_data_changed = true;
+        }
+        EventResult::Consumed(None)
+    }
+    
+    fn undo(&mut self) -> EventResult {
+        if let Some(offset) = self.reader.undo() {
+            self.half_byte_written = false;
+            self.move_cursor(offset);
+            self.invalidated_data_changed = true;
+        }
+        EventResult::Consumed(None)
+    }
+    
+    fn redo(&mut self) -> EventResult {
+        if let Some(offset) = self.reader.redo() {
+            self.half_byte_written = false;
+            self.move_cursor(offset);
+            self.invalidated_data_changed = true;
+        }
+        EventResult::Consumed(None)
+    }
+    
+    fn advance_cursor(&mut self) {
+        let next = self.reader.cursor + 1;
+        if next <= self.last_cursor_offset() {
+            self.move_cursor(next);
+        }
+    }
+    
+    fn next_match(&mut self) -> EventResult {
+        if let Some(offset) = self.reader.next_match(self.reader.cursor) {
+            self.go_to_match(offset);
+        }
+        EventResult::Consumed(None)
+    }
+    
+    fn previous_match(&mut self) -> EventResult {
+        if let Some(offset) = self.reader.previous_match(self.reader.cursor) {
+            self.go_to_match(offset);
+        }
+        EventResult::Consumed(None)
+    }
+    
+    fn go_to_match(&mut self, offset: u64) {
+        self.reader.selection_anchor = None;
+        self.move_cursor(offset);
     }
     
     fn toggle_visual(&mut self) -> EventResult {
@@ -129,6 +596,9 @@ impl HexView {
             VisualMode::Ascii => {
                 self.reader.set_visual_mode(VisualMode::Off);
                 self.show_visual_view = false;
+                if self.edit_column == Some(EditColumn::Visual) {
+                    self.edit_column = Some(EditColumn::Hex);
+                }
                 self.invalidated_resize = true;
             },
             VisualMode::Off => {
@@ -140,6 +610,19 @@ impl HexView {
         EventResult::Consumed(None)
     }
     
+    fn toggle_inspector(&mut self) -> EventResult {
+        self.show_inspector = !self.show_inspector;
+        self.invalidated_resize = true;
+        EventResult::Consumed(None)
+    }
+    
+    fn next_inspector_page(&mut self) -> EventResult {
+        if self.show_inspector {
+            self.inspector_page = (self.inspector_page + 1) % self.inspector_page_count();
+        }
+        EventResult::Consumed(None)
+    }
+    
     fn reload_data(&mut self) -> EventResult {
         self.reader.clear_highlights();
         self.reader.capture_before_image();
@@ -148,7 +631,9 @@ impl HexView {
     }
     
     fn reopen_and_reload_data(&mut self) -> EventResult {
-        self.reader.reopen().unwrap();
+        if let Err(error) = self.reader.reopen() {
+            self.message = Some(format!("Re-opening failed: {}", error));
+        }
         self.reload_data()
     }
     
@@ -163,37 +648,177 @@ impl HexView {
             'l' => self.on_key_event(Key::Right),
             'L' => self.on_key_event(Key::End),
             'v' => self.toggle_visual(),
+            'i' => self.toggle_inspector(),
+            'I' => self.next_inspector_page(),
+            'n' => self.next_match(),
+            'N' => self.previous_match(),
+            'e' => self.start_editing(),
+            'u' => self.undo(),
+            'U' => self.redo(),
             'r' => self.reload_data(),
             'R' => self.reopen_and_reload_data(),
             _ => EventResult::Ignored
         }
     }
     
-    fn on_mouse_event(&mut self, _offset: Vec2, _position: Vec2, event: MouseEvent) -> EventResult {
+    fn on_mouse_event(&mut self, offset: Vec2, position: Vec2, event: MouseEvent) -> EventResult {
         match event {
-            MouseEvent::WheelUp => self.on_key_event(Key::Up),
-            MouseEvent::WheelDown => self.on_key_event(Key::Down),
+            MouseEvent::WheelUp => self.navigate((0, -1)),
+            MouseEvent::WheelDown => self.navigate((0, 1)),
+            MouseEvent::Press(MouseButton::Left) => {
+                match position.checked_sub(offset).and_then(|p| self.offset_at(p)) {
+                    Some(target) => {
+                        self.reader.selection_anchor = None;
+                        self.move_cursor(target)
+                    },
+                    None => EventResult::Ignored
+                }
+            },
+            MouseEvent::Hold(MouseButton::Left) => {
+                // Dragging selects from where the button was pressed.
+                match position.checked_sub(offset).and_then(|p| self.offset_at(p)) {
+                    Some(target) => {
+                        let anchor = self.reader.selection_anchor.unwrap_or(self.reader.cursor);
+                        self.reader.selection_anchor = Some(anchor);
+                        self.move_cursor(target)
+                    },
+                    None => EventResult::Consumed(None)
+                }
+            },
             _ => EventResult::Ignored
         }
     }
     
     fn on_key_event(&mut self, k: Key) -> EventResult {
-        let inner_height = i64::try_from(self.offsets_column_size.y).unwrap();
-        let line_width = i64::try_from(self.reader.line_width).unwrap();
-        let pos_x = i64::try_from(self.reader.window_pos.0).unwrap();
-        let size_x = i64::from(self.reader.window_size.0);
-        let offset = match k {
-            Key::Down => (0, 1),
-            Key::Up => (0, -1),
-            Key::Left => (-1, 0),
-            Key::Right => (1, 0),
-            Key::PageDown => (0, inner_height),
-            Key::PageUp => (0, -inner_height),
-            Key::Home => (-pos_x, 0),
-            Key::End => (line_width - size_x - pos_x, 0),
-            _ => (0, 0)
+        let result = self.move_by_key(k);
+        if result.is_consumed() {
+            self.reader.selection_anchor = None;
+        }
+        result
+    }
+    
+    fn on_shift_key_event(&mut self, k: Key) -> EventResult {
+        let anchor = self.reader.selection_anchor.unwrap_or(self.reader.cursor);
+        let result = self.move_by_key(k);
+        if result.is_consumed() {
+            self.reader.selection_anchor = Some(anchor);
+        }
+        result
+    }
+    
+    fn move_by_key(&mut self, k: Key) -> EventResult {
+        let inner_height = u64::try_from(self.offsets_column_size.y).unwrap();
+        let line_width = self.reader.line_width;
+        let cursor = self.reader.cursor;
+        let line_start = cursor - cursor % line_width;
+        match k {
+            Key::Down => self.move_cursor(cursor + line_width),
+            Key::Up if cursor >= line_width => self.move_cursor(cursor - line_width),
+            Key::Left if cursor > line_start => self.move_cursor(cursor - 1),
+            Key::Right if cursor + 1 < line_start + line_width => self.move_cursor(cursor + 1),
+            Key::PageDown => {
+                self.navigate((0, i64::try_from(inner_height).unwrap()));
+                self.move_cursor(cursor + inner_height * line_width)
+            },
+            Key::PageUp => {
+                self.navigate((0, -i64::try_from(inner_height).unwrap()));
+                self.move_cursor(cursor.saturating_sub(inner_height * line_width))
+            },
+            Key::Home => self.move_cursor(line_start),
+            Key::End => self.move_cursor(line_start + line_width - 1),
+            Key::Up | Key::Left | Key::Right => EventResult::Consumed(None),
+            _ => EventResult::Ignored
+        }
+    }
+    
+    fn clamp_to_file(&self, offset: u64) -> u64 {
+        let length = self.reader.get_length();
+        if offset < length { offset } else { length.saturating_sub(1) }
+    }
+    
+    /// The cursor can be placed just past the last byte while inserting, so bytes can be appended.
+    fn last_cursor_offset(&self) -> u64 {
+        let length = self.reader.get_length();
+        if self.edit_column.is_some() && self.insert_mode { length } else { length.saturating_sub(1) }
+    }
+    
+    fn move_cursor(&mut self, target: u64) -> EventResult {
+        self.half_byte_written = false;
+        self.reader.cursor = target.min(self.last_cursor_offset());
+        self.scroll_to_cursor();
+        EventResult::Consumed(None)
+    }
+    
+    fn scroll_to_cursor(&mut self) {
+        let line_width = self.reader.line_width;
+        let line = self.reader.cursor / line_width;
+        let column = self.reader.cursor % line_width;
+        let (x, y) = self.reader.window_pos;
+        let w = u64::from(self.reader.window_size.0).max(1);
+        let h = u64::from(self.reader.window_size.1).max(1);
+
+        let new_x = if column < x {
+            column
+        } else if column >= x + w {
+            column + 1 - w
+        } else {
+            x
+        };
+        let new_y = if line < y {
+            line
+        } else if line >= y + h {
+            line + 1 - h
+        } else {
+            y
+        };
+        
+        if (new_x, new_y) != (x, y) {
+            self.reader.window_pos = (new_x, new_y);
+            self.invalidated_resize = true;
+            self.invalidated_data_changed = true;
+        }
+    }
+    
+    /// Find the file offset of the byte drawn at the given position, relative to the view.
+    fn offset_at(&self, pos: Vec2) -> Option<u64> {
+        let in_column = |col_pos: Vec2, col_size: Vec2| {
+            pos.x >= col_pos.x && pos.x < col_pos.x + col_size.x &&
+                pos.y >= col_pos.y && pos.y < col_pos.y + col_size.y
         };
-        self.navigate(offset)
+        let index = if in_column(self.hex_column_pos, self.hex_column_size) {
+            u64::try_from((pos.x - self.hex_column_pos.x) / 3).unwrap()
+        } else if self.show_visual_view && in_column(self.visual_column_pos, self.visual_column_size) {
+            self.visual_index_at(pos.x - self.visual_column_pos.x)?
+        } else {
+            return None;
+        };
+        if index >= u64::from(self.reader.window_size.0) {
+            return None;
+        }
+        
+        let row = u64::try_from(pos.y - self.hex_column_pos.y).unwrap();
+        let (x, y) = self.reader.window_pos;
+        let offset = (y + row) * self.reader.line_width + x + index;
+        if offset < self.reader.get_length() { Some(offset) } else { None }
+    }
+    
+    fn visual_index_at(&self, target_x: usize) -> Option<u64> {
+        // Each byte takes up one cell in the visual column, and the group separators take up one more.
+        let group = u64::from(self.reader.group);
+        let mut x = 0;
+        for index in 0..u64::from(self.reader.window_size.0) {
+            if x == target_x {
+                return Some(index);
+            }
+            x += 1;
+            if (self.reader.window_pos.0 + index + 1) % group == 0 {
+                if x == target_x {
+                    return Some(index);
+                }
+                x += 1;
+            }
+        }
+        None
     }
     
     fn navigate(&mut self, offset: (i64, i64)) -> EventResult {
@@ -202,19 +827,19 @@ impl HexView {
             if x < 0 {
                 let diff = u64::try_from(-x).unwrap();
                 let curr = self.reader.window_pos.0;
-                self.reader.window_pos.0 = if curr < diff { 0 } else { curr - diff };
+                self.reader.window_pos.0 = curr.saturating_sub(diff);
             } else {
                 let diff = u64::try_from(x).unwrap();
                 let next = self.reader.window_pos.0 + diff;
                 let line = self.reader.line_width;
                 let width = u64::from(self.reader.window_size.0);
-                let max = if width > line { 0 } else { line - width };
+                let max = line.saturating_sub(width);
                 self.reader.window_pos.0 = if next > max { max } else { next };
             }
             if y < 0 {
                 let diff = u64::try_from(-y).unwrap();
                 let curr = self.reader.window_pos.1;
-                self.reader.window_pos.1 = if curr < diff { 0 } else { curr - diff };
+                self.reader.window_pos.1 = curr.saturating_sub(diff);
             } else {
                 let diff = u64::try_from(y).unwrap();
                 let next = self.reader.window_pos.1 + diff;
@@ -237,7 +862,8 @@ impl HexView {
     
     fn draw_title(&self, printer: &Printer) {
         let title = self.reader.file_name();
-        let mut len = title.width();
+        let title_width = title.width();
+        let mut len = title_width;
         let container_width = printer.size.x;
         let spacing = 3;
         let spacing_both_ends = 2 * spacing;
@@ -251,8 +877,17 @@ impl HexView {
         });
 
         printer.with_color(ColorStyle::title_primary(), |p| {
-            if len < title.len() {
-                p.print((offset, 0), &title[0..len]);
+            if len < title_width {
+                // Cut the title at a character boundary, leaving room for the ellipsis.
+                let mut width = 0;
+                let end = title.char_indices()
+                    .take_while(|(_, c)| {
+                        width += c.width().unwrap_or(0);
+                        width < len
+                    })
+                    .last()
+                    .map_or(0, |(i, c)| i + c.len_utf8());
+                p.print((offset, 0), &title[0..end]);
                 p.print((offset + len - 1, 0), "…");
             } else {
                 p.print((offset, 0), title);
@@ -260,6 +895,97 @@ impl HexView {
         });
     }
     
+    fn update_status(&self) {
+        let mut status = String::new();
+        if let Some(message) = &self.message {
+            status.push_str(message);
+            status.push_str("   ");
+        }
+        if self.edit_column.is_some() {
+            status.push_str(if self.insert_mode { "INSERT " } else { "EDIT " });
+        }
+        match self.edit_column {
+            Some(EditColumn::Hex) => status.push_str("HEX   "),
+            Some(EditColumn::Visual) => status.push_str("TEXT   "),
+            None => (),
+        }
+        if self.reader.input_progress().is_some_and(|p| !p.is_finished()) {
+            let receiving = if self.reader.compression().is_some() {
+                "Decompressing"
+            } else if self.reader.member_name().is_some() {
+                "Extracting"
+            } else {
+                "Receiving input"
+            };
+            status.push_str(&format!("{}: {} bytes   ", receiving, self.reader.get_length()));
+        }
+        if self.reader.has_changes() {
+            status.push_str("Modified   ");
+        }
+        let undo_count = self.reader.undo_count();
+        let redo_count = self.reader.redo_count();
+        if undo_count > 0 || redo_count > 0 {
+            status.push_str(&format!("Undo: {}  Redo: {}   ", undo_count, redo_count));
+        }
+        if let Some(search) = &self.search {
+            let percent = (search.searched * 100).checked_div(search.length).unwrap_or(100);
+            status.push_str(&format!("Searching {}% (Esc to cancel)   ", percent));
+        }
+        let match_count = self.reader.match_count();
+        if match_count > 0 {
+            match self.reader.match_index_at(self.reader.cursor) {
+                Some(index) => status.push_str(&format!("Match {} of {}   ", index + 1, match_count)),
+                None => status.push_str(&format!("{} matches   ", match_count)),
+            }
+        }
+        if let Some((start, length)) = self.reader.selection() {
+            status.push_str(&format!("Selected: 0x{:X} ({}) +{}   ", start, start, length));
+        }
+        let cursor = self.reader.cursor;
+        if let Some(address) = self.reader.address_at(cursor) {
+            status.push_str(&format!("Address: 0x{:X}   ", address));
+        }
+        status.push_str(&format!("0x{:X} ({})", cursor, cursor));
+        self.status.set_content(status);
+    }
+    
+    fn update_inspector(&mut self) {
+        let mut buf = [0; INSPECT_LENGTH];
+        let length = self.reader.peek(self.reader.cursor, &mut buf).unwrap_or(0);
+        self.inspector_rows = inspect(&buf[..length]);
+        // The panel may have become taller, and have fewer pages.
+        self.inspector_page = self.inspector_page.min(self.inspector_page_count() - 1);
+    }
+    
+    /// When not all rows fit in the panel, the last line of the panel shows which page of rows is shown.
+    fn inspector_rows_per_page(&self) -> usize {
+        let height = self.inspector_column_size.y;
+        if self.inspector_rows.len() <= height { height } else { height.saturating_sub(1).max(1) }
+    }
+    
+    fn inspector_page_count(&self) -> usize {
+        let rows_per_page = self.inspector_rows_per_page().max(1);
+        self.inspector_rows.len().div_ceil(rows_per_page).max(1)
+    }
+    
+    fn draw_inspector(&self, printer: &Printer) {
+        let border_offset = self.inspector_column_pos.x - 1;
+        printer.print_vline(Vec2::new(border_offset, 1), self.inspector_column_size.y, "│");
+        
+        let printer = printer.offset(self.inspector_column_pos).cropped(self.inspector_column_size);
+        let rows_per_page = self.inspector_rows_per_page();
+        let rows = self.inspector_rows.iter().skip(self.inspector_page * rows_per_page).take(rows_per_page);
+        for (y, (label, value)) in rows.enumerate() {
+            printer.with_color(ColorStyle::title_secondary(), |p| p.print((0, y), label));
+            printer.with_color(ColorStyle::primary(), |p| p.print((INSPECTOR_LABEL_WIDTH, y), value));
+        }
+        let page_count = self.inspector_page_count();
+        if page_count > 1 {
+            let footer = format!("page {}/{}, I: next page", self.inspector_page + 1, page_count);
+            printer.with_color(ColorStyle::secondary(), |p| p.print((0, rows_per_page), &footer));
+        }
+    }
+    
     fn build_prestyled_hex_table(&mut self) {
         self.reader.generate_hex_tables(&mut self.hex_tables);
     }
@@ -305,6 +1031,10 @@ impl View for HexView {
             };
             self.reader.visit_hex(&mut visual_printer);
         }
+        
+        if self.show_inspector {
+            self.draw_inspector(printer);
+        }
     }
 
     fn layout(&mut self, constraint: Vec2) {
@@ -314,6 +1044,10 @@ impl View for HexView {
         if self.visual_tables.is_empty() {
             self.build_prestyled_visual_table();
         }
+        if self.reader.get_row_offsets_width() != self.offsets_column_size.x {
+            // The file grew or shrunk past the limit of the small addresses.
+            self.invalidated_resize = true;
+        }
         if self.invalidated_resize {
             // The viewing area changed size, or the visual column was toggled.
 
@@ -332,7 +1066,14 @@ impl View for HexView {
             // Box-border, offsets column, separator line + space line:
             let hex_col_start = 1 + colw_offsets + 2;
             self.hex_column_pos = Vec2::new(hex_col_start, 1);
-            self.hex_column_size = Vec2::new(constraint.x - hex_col_start - 1, inner_height);
+            let inspector_width = if self.show_inspector { INSPECTOR_WIDTH + 1 } else { 0 };
+            let min_hex_col_end = if self.show_inspector { hex_col_start + MIN_HEX_COLUMN_WIDTH } else { hex_col_start };
+            // On a narrow screen the inspector gets cropped, rather than leave room for only a few bytes per line.
+            let hex_col_end = constraint.x.saturating_sub(1 + inspector_width).max(min_hex_col_end.min(constraint.x.saturating_sub(2)))
+                .max(hex_col_start);
+            self.hex_column_size = Vec2::new(hex_col_end - hex_col_start, inner_height);
+            self.inspector_column_pos = Vec2::new(hex_col_end + 1, 1);
+            self.inspector_column_size = Vec2::new(INSPECTOR_WIDTH.min(constraint.x.saturating_sub(hex_col_end + 2)), inner_height);
 
             let group = u64::from(self.reader.group);
             let reader_pos_x = group - 1;
@@ -394,9 +1135,16 @@ impl View for HexView {
 
         if self.invalidated_data_changed {
             // The viewing area was moved or changed size.
-            self.reader.capture().unwrap();
+            if let Err(error) = self.reader.capture() {
+                self.message = Some(format!("Reading failed: {}", error));
+            }
             self.invalidated_data_changed = false;
         }
+        
+        if self.show_inspector {
+            self.update_inspector();
+        }
+        self.update_status();
     }
 
     fn needs_relayout(&self) -> bool {
@@ -408,13 +1156,26 @@ impl View for HexView {
     }
 
     fn on_event(&mut self, event: Event) -> EventResult {
+        self.message = None;
         match event {
             Event::WindowResize => {
                 self.invalidated_resize = true;
                 EventResult::Consumed(None)
             },
-            Event::Char(c) => self.on_char_event(c),
+            Event::Char(c) => match self.edit_column {
+                Some(column) => self.on_edit_char(column, c),
+                None => self.on_char_event(c),
+            },
+            Event::CtrlChar('z') => self.undo(),
+            Event::CtrlChar('y') => self.redo(),
+            Event::Key(Key::Esc) if self.search.is_some() => self.cancel_search(),
+            Event::Key(Key::Esc) if self.edit_column.is_some() => self.stop_editing(),
+            Event::Key(Key::Tab) if self.edit_column.is_some() => self.switch_edit_column(),
+            Event::Key(Key::Ins) if self.edit_column.is_some() => self.toggle_insert_mode(),
+            Event::Key(Key::Del) if self.edit_column.is_some() => self.delete_at_cursor(),
+            Event::Key(Key::Backspace) if self.edit_column.is_some() => self.delete_before_cursor(),
             Event::Key(k) => self.on_key_event(k),
+            Event::Shift(k) => self.on_shift_key_event(k),
             Event::Mouse { offset, position, event } => self.on_mouse_event(offset, position, event),
             _ => EventResult::Ignored
         }
@@ -423,16 +1184,17 @@ impl View for HexView {
 
 #[cfg(test)]
 mod tests {
-    use std::io::Write;
+    use std::io::{Seek, SeekFrom, Write};
 
     use crate::byte_reader::TilingByteReader;
+    use crate::search_matcher::LiteralMatcher;
 
     use super::*;
 
     #[test]
     fn layout_w80_h24_ll16() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -459,7 +1221,7 @@ mod tests {
     #[test]
     fn layout_w80_h24_ll32() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -486,7 +1248,7 @@ mod tests {
     #[test]
     fn layout_w79_h24_ll16() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -512,7 +1274,7 @@ mod tests {
     #[test]
     fn layout_w78_h24_ll16() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -538,7 +1300,7 @@ mod tests {
     #[test]
     fn layout_w77_h24_ll16() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -564,7 +1326,7 @@ mod tests {
     #[test]
     fn layout_w77_h24_ll16_offset1() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -591,7 +1353,7 @@ mod tests {
     #[test]
     fn layout_w82_h24_ll32() {
         let mut tmpf = tempfile::NamedTempFile::new().unwrap();
-        tmpf.write(b"0123456789abcdef0123456789abcdef0123456789abcdef").unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef0123456789abcdef").unwrap();
 
         let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
         let hex_reader = HexReader::new(byte_reader).unwrap();
@@ -632,4 +1394,396 @@ mod tests {
         assert_eq!(view.visual_column_pos, Vec2::new(62, 1));
         assert_eq!(view.visual_column_size, Vec2::new(18, 21));
     }
+
+    #[test]
+    fn moving_cursor_scrolls_viewport() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(&[0; 1024]).unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+
+        let constraint = Vec2::new(80, 12);
+        view.layout(constraint);
+        assert_eq!(view.reader.window_size, (16, 10));
+
+        for _ in 0..9 {
+            view.on_key_event(Key::Down);
+        }
+        assert_eq!(view.reader.cursor, 9 * 16);
+        assert_eq!(view.reader.window_pos, (0, 0));
+        
+        view.on_key_event(Key::Down);
+        view.on_key_event(Key::End);
+        assert_eq!(view.reader.cursor, 10 * 16 + 15);
+        assert_eq!(view.reader.window_pos, (0, 1));
+        
+        view.on_key_event(Key::Right);
+        assert_eq!(view.reader.cursor, 10 * 16 + 15);
+        
+        view.go_to_offset(2000);
+        assert_eq!(view.reader.cursor, 1023);
+        view.go_to_offset(3);
+        assert_eq!(view.reader.cursor, 3);
+        assert_eq!(view.reader.window_pos, (0, 0));
+    }
+
+    #[test]
+    fn mouse_position_to_offset() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(&[0; 1024]).unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+
+        let constraint = Vec2::new(80, 12);
+        view.layout(constraint);
+
+        assert_eq!(view.offset_at(Vec2::new(13, 1)), Some(0));
+        assert_eq!(view.offset_at(Vec2::new(15, 1)), Some(0));
+        assert_eq!(view.offset_at(Vec2::new(16, 2)), Some(17));
+        assert_eq!(view.offset_at(Vec2::new(61, 1)), Some(0));
+        assert_eq!(view.offset_at(Vec2::new(68, 1)), Some(7));
+        assert_eq!(view.offset_at(Vec2::new(70, 1)), Some(8));
+        assert_eq!(view.offset_at(Vec2::new(5, 1)), None);
+    }
+
+    #[test]
+    fn editing_hex_and_text() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+
+        view.on_event(Event::Char('e'));
+        view.on_event(Event::Char('f'));
+        assert_eq!(view.reader.cursor, 0);
+        view.on_event(Event::Char('x')); // Not a hex digit, so ignored.
+        view.on_event(Event::Char('e'));
+        assert_eq!(view.reader.cursor, 1);
+        view.on_event(Event::Key(Key::Tab));
+        view.on_event(Event::Char('q'));
+        view.on_event(Event::Key(Key::Esc));
+        view.on_event(Event::Char('l'));
+        assert_eq!(view.reader.cursor, 3);
+        assert!(view.has_unsaved_changes());
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"0123456789abcdef");
+
+        view.save().unwrap();
+        assert!(!view.has_unsaved_changes());
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"\xFEq23456789abcdef");
+    }
+
+    #[test]
+    fn saving_between_the_digits_of_a_byte() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+
+        view.on_event(Event::Char('e'));
+        view.on_event(Event::Char('a'));
+        view.save().unwrap();
+        assert!(!view.has_unsaved_changes());
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"\xA0123");
+        view.on_event(Event::Char('b'));
+        assert!(view.has_unsaved_changes());
+        view.save().unwrap();
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"\xB0123");
+    }
+
+    #[test]
+    fn undo_moves_cursor_to_edit() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(&[0; 1024]).unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+
+        view.go_to_offset(20);
+        view.on_event(Event::Char('e'));
+        view.on_event(Event::Char('1'));
+        view.on_event(Event::Char('2'));
+        view.on_event(Event::Char('3'));
+        view.on_event(Event::Key(Key::Esc));
+        view.go_to_offset(1000);
+        assert_eq!(view.reader.undo_count(), 2);
+
+        view.on_event(Event::Char('u'));
+        assert_eq!(view.reader.cursor, 21);
+        assert_eq!(view.reader.read_byte(21).unwrap(), 0);
+        view.on_event(Event::CtrlChar('z'));
+        assert_eq!(view.reader.cursor, 20);
+        assert_eq!(view.reader.read_byte(20).unwrap(), 0);
+        assert!(!view.has_unsaved_changes());
+        
+        view.on_event(Event::Char('U'));
+        assert_eq!(view.reader.read_byte(20).unwrap(), 0x12);
+        assert_eq!(view.reader.window_pos, (0, 1));
+    }
+
+    #[test]
+    fn inserting_and_deleting_bytes() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+
+        view.on_event(Event::Char('e'));
+        view.on_event(Event::Key(Key::Ins));
+        view.on_event(Event::Char('a'));
+        view.on_event(Event::Char('b'));
+        view.on_event(Event::Key(Key::Tab));
+        view.on_event(Event::Char('Z'));
+        assert_eq!(view.reader.cursor, 2);
+        assert_eq!(view.get_length(), 18);
+        view.on_event(Event::Key(Key::Del));
+        view.on_event(Event::Key(Key::Backspace));
+        assert_eq!(view.reader.cursor, 1);
+        assert_eq!(view.get_length(), 16);
+        view.layout(Vec2::new(80, 12));
+        
+        let mut hex = String::new();
+        view.reader.visit_hex(&mut hex);
+        assert_eq!(hex, "ab* 31# 32 33 34 35 36 37 38 39 61 62 63 64 65 66");
+
+        view.save().unwrap();
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"\xAB123456789abcdef");
+    }
+
+    #[test]
+    fn appending_bytes_in_insert_mode() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+
+        view.on_event(Event::Key(Key::End));
+        assert_eq!(view.reader.cursor, 3);
+        view.on_event(Event::Char('e'));
+        view.on_event(Event::Key(Key::Right));
+        assert_eq!(view.reader.cursor, 3);
+        view.on_event(Event::Key(Key::Ins));
+        view.on_event(Event::Key(Key::Right));
+        assert_eq!(view.reader.cursor, 4);
+        view.on_event(Event::Char('4'));
+        view.on_event(Event::Char('1'));
+        view.on_event(Event::Key(Key::Tab));
+        view.on_event(Event::Char('B'));
+        assert_eq!(view.reader.cursor, 6);
+        assert_eq!(view.get_length(), 6);
+        view.layout(Vec2::new(80, 12));
+        let mut hex = String::new();
+        view.reader.visit_hex(&mut hex);
+        assert_eq!(hex, "30 31 32 33 41* 42* __#");
+
+        view.on_event(Event::Key(Key::Esc));
+        assert_eq!(view.reader.cursor, 5);
+        view.save().unwrap();
+        assert_eq!(std::fs::read(tmpf.path()).unwrap(), b"0123AB");
+    }
+
+    #[test]
+    fn selecting_with_keyboard_and_mouse() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"0123456789abcdef0123456789abcdef").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+
+        view.on_event(Event::Key(Key::Right));
+        view.on_event(Event::Shift(Key::Right));
+        view.on_event(Event::Shift(Key::Down));
+        assert_eq!(view.reader.selection(), Some((1, 18)));
+        view.on_event(Event::Shift(Key::Up));
+        view.on_event(Event::Shift(Key::Left));
+        view.on_event(Event::Shift(Key::Left));
+        assert_eq!(view.reader.selection(), Some((0, 2)));
+        view.on_event(Event::Key(Key::Right));
+        assert_eq!(view.reader.selection(), None);
+
+        let mouse = |event, x| Event::Mouse { offset: Vec2::zero(), position: Vec2::new(x, 1), event };
+        view.on_event(mouse(MouseEvent::Press(MouseButton::Left), 16));
+        view.on_event(mouse(MouseEvent::Hold(MouseButton::Left), 22));
+        view.on_event(mouse(MouseEvent::Hold(MouseButton::Left), 25));
+        view.on_event(mouse(MouseEvent::Release(MouseButton::Left), 25));
+        assert_eq!(view.reader.selection(), Some((1, 4)));
+        assert_eq!(view.reader.cursor, 4);
+        view.on_event(mouse(MouseEvent::Press(MouseButton::Left), 16));
+        assert_eq!(view.reader.selection(), None);
+    }
+
+    #[test]
+    fn inspector_follows_the_cursor() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"\x01\x02\x03\x04").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.on_event(Event::Char('i'));
+        view.layout(Vec2::new(80, 12));
+        
+        assert_eq!(view.reader.window_size.0, 8);
+        assert_eq!(view.inspector_column_pos, Vec2::new(48, 1));
+        assert_eq!(view.inspector_column_size, Vec2::new(31, 10));
+        assert_eq!(view.inspector_rows[4], ("i16 BE", "258".to_string()));
+        
+        view.on_event(Event::Key(Key::Right));
+        view.layout(Vec2::new(80, 12));
+        assert_eq!(view.inspector_rows[4], ("i16 BE", "515".to_string()));
+    }
+
+    #[test]
+    fn inspector_rows_are_paged_when_they_do_not_fit() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"\x01\x02\x03\x04").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.on_event(Event::Char('i'));
+        view.layout(Vec2::new(100, 12));
+        assert_eq!(view.inspector_rows_per_page(), 9);
+        assert_eq!(view.inspector_page_count(), 4);
+        
+        for page in &[1, 2, 3, 0] {
+            view.on_event(Event::Char('I'));
+            assert_eq!(view.inspector_page, *page);
+        }
+        view.on_event(Event::Char('I'));
+        view.on_event(Event::Char('I'));
+        view.on_event(Event::Char('I'));
+        // When the panel grows, the page is kept within the fewer pages there are.
+        view.on_event(Event::WindowResize);
+        view.layout(Vec2::new(100, 24));
+        assert_eq!(view.inspector_page_count(), 2);
+        assert_eq!(view.inspector_page, 1);
+        view.on_event(Event::WindowResize);
+        view.layout(Vec2::new(100, 40));
+        assert_eq!(view.inspector_page_count(), 1);
+        assert_eq!(view.inspector_page, 0);
+    }
+
+    #[test]
+    fn navigating_between_matches() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(&[0; 1024]).unwrap();
+        tmpf.as_file_mut().seek(SeekFrom::Start(16)).unwrap();
+        tmpf.write_all(b"needle").unwrap();
+        tmpf.as_file_mut().seek(SeekFrom::Start(1000)).unwrap();
+        tmpf.write_all(b"needle").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+        
+        view.search_generation = 1;
+        view.search_origin = Some(0);
+        view.add_search_results(SearchResults {
+            generation: 1,
+            matches: vec![(16, 6), (1000, 6)],
+            searched: 1024,
+            finished: true,
+            error: None,
+        });
+        assert_eq!(view.reader.cursor, 16);
+        view.layout(Vec2::new(80, 12));
+        assert!(view.status.get_content().source().starts_with("Match 1 of 2   "));
+        
+        view.on_event(Event::Char('n'));
+        assert_eq!(view.reader.cursor, 1000);
+        assert_eq!(view.reader.window_pos, (0, 53));
+        view.on_event(Event::Char('n'));
+        assert_eq!(view.reader.cursor, 16);
+        view.on_event(Event::Char('N'));
+        assert_eq!(view.reader.cursor, 1000);
+        view.on_event(Event::Key(Key::Left));
+        view.layout(Vec2::new(80, 12));
+        assert!(view.status.get_content().source().starts_with("2 matches   "));
+    }
+
+    #[test]
+    fn replacing_matches() {
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(b"/tmp/a /tmp/b /tmp/c /tmpx").unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+        
+        view.search_generation = 1;
+        view.add_search_results(SearchResults {
+            generation: 1,
+            matches: vec![(0, 5), (7, 5), (14, 5), (21, 4)],
+            searched: 25,
+            finished: true,
+            error: None,
+        });
+        view.go_to_offset(3);
+        assert_eq!(view.replace_next_match(b"/var/").unwrap(), Some(7));
+        assert_eq!(view.reader.cursor, 14);
+        assert_eq!(view.search_matches(), vec![(0, 5), (14, 5), (21, 4)]);
+        assert!(view.replace_next_match(b"/x").is_err());
+        
+        assert_eq!(view.replace_all_matches(b"/usr/").unwrap(), (2, 1));
+        assert_eq!(view.read_range(0, 26).unwrap(), b"/usr/a /var/b /usr/c /tmpx");
+        assert_eq!(view.replace_next_match(b"/usr/").unwrap(), None);
+        assert!(view.has_unsaved_changes());
+        
+        // Replacing all matches is undone at once.
+        view.on_event(Event::Char('u'));
+        assert_eq!(view.read_range(0, 26).unwrap(), b"/tmp/a /var/b /tmp/c /tmpx");
+        assert_eq!(view.reader.undo_count(), 1);
+    }
+
+    #[test]
+    fn previewing_a_search() {
+        let mut data = vec![b'.'; 4096];
+        for &offset in &[5, 40, 3000] {
+            data[offset..offset + 3].copy_from_slice(b"abc");
+        }
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(&data).unwrap();
+
+        let byte_reader = TilingByteReader::new(tmpf.path()).unwrap();
+        let hex_reader = HexReader::new(byte_reader).unwrap();
+        let mut view = HexView::new(hex_reader);
+        view.layout(Vec2::new(80, 12));
+        let matcher = LiteralMatcher::new(b"abc");
+        
+        view.go_to_offset(10);
+        view.preview_search(&matcher).unwrap();
+        assert_eq!(view.reader.cursor, 40);
+        assert_eq!(view.search_matches(), vec![(5, 3), (40, 3)]);
+        view.end_preview();
+        assert_eq!(view.reader.cursor, 10);
+        assert!(view.search_matches().is_empty());
+        
+        view.go_to_offset(41);
+        view.preview_search(&matcher).unwrap();
+        view.preview_search(&matcher).unwrap();
+        assert_eq!(view.reader.cursor, 3000);
+        assert_eq!(view.search_matches(), vec![(3000, 3)]);
+    }
 }
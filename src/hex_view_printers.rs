@@ -1,5 +1,5 @@
 use cursive::{Printer, Vec2};
-use cursive::theme::{ColorStyle, Style};
+use cursive::theme::{ColorStyle, Effect, PaletteColor, Style};
 use cursive::utils::markup::StyledString;
 use cursive::utils::span::{IndexedCow, IndexedSpan, SpannedStr};
 use unicode_width::UnicodeWidthStr;
@@ -7,7 +7,9 @@ use unicode_width::UnicodeWidthStr;
 use crate::hex_reader::{HexVisitor, Highlight, OffsetsVisitor};
 use crate::hex_tables::ByteCategory;
 
-const GROUP_SEP: &str = "\u{00A6}";
+pub const GROUP_SEP: &str = "\u{00A6}";
+/// The index of the cursor drawn past the last byte, after the 256 byte values.
+pub const END_OF_FILE: usize = 256;
 
 pub struct OffsetPrinter<'a, 'b, 'x> {
     pub pos: Vec2,
@@ -38,6 +40,11 @@ pub struct TableSet {
     pub neu: Vec<StyledString>,
     pub pos: Vec<StyledString>,
     pub neg: Vec<StyledString>,
+    pub cur: Vec<StyledString>,
+    pub sel: Vec<StyledString>,
+    pub edit: Vec<StyledString>,
+    /// Bytes that could not be read have no value, so they are all shown the same way, at index 0.
+    pub unreadable: Vec<StyledString>,
 }
 
 impl TableSet {
@@ -45,7 +52,11 @@ impl TableSet {
         TableSet {
             neu: Vec::new(),
             pos: Vec::new(),
-            neg: Vec::new()
+            neg: Vec::new(),
+            cur: Vec::new(),
+            sel: Vec::new(),
+            edit: Vec::new(),
+            unreadable: Vec::new(),
         }
     }
     
@@ -53,12 +64,40 @@ impl TableSet {
         self.neu.push(StyledString::styled(s, category_to_color(category)));
         self.pos.push(StyledString::styled(s, ColorStyle::highlight_inactive()));
         self.neg.push(StyledString::styled(s, ColorStyle::highlight()));
+        self.cur.push(StyledString::styled(s, Style::from(category_to_color(category)).combine(Effect::Reverse)));
+        self.sel.push(StyledString::styled(s, ColorStyle::new(category_to_color(category).front, PaletteColor::Tertiary)));
+        self.edit.push(StyledString::styled(s, Style::from(ColorStyle::front(PaletteColor::Highlight)).combine(Effect::Underline)));
+    }
+    
+    pub fn push_unreadable(&mut self, s: &'static str) {
+        self.unreadable.push(StyledString::styled(s, ColorStyle::secondary()));
+    }
+    
+    /// The cursor past the last byte is drawn at index `END_OF_FILE` of the cursor table.
+    pub fn push_end_of_file(&mut self, s: &'static str) {
+        self.cur.push(StyledString::styled(s, Style::from(ColorStyle::primary()).combine(Effect::Reverse)));
+    }
+    
+    pub fn get(&self, highlight: Highlight) -> &[StyledString] {
+        match highlight {
+            Highlight::Neutral => &self.neu,
+            Highlight::Positive => &self.pos,
+            Highlight::Negative => &self.neg,
+            Highlight::Cursor => &self.cur,
+            Highlight::Selected => &self.sel,
+            Highlight::Edited => &self.edit,
+            Highlight::Unreadable => &self.unreadable,
+        }
     }
     
     pub fn clear(&mut self) {
         self.neu.clear();
         self.pos.clear();
         self.neg.clear();
+        self.cur.clear();
+        self.sel.clear();
+        self.edit.clear();
+        self.unreadable.clear();
     }
     
     pub fn is_empty(&self) -> bool {
@@ -87,11 +126,7 @@ impl<'a, 'b, 'x> HexVisitor for HexPrinter<'a, 'b, 'x> {
         if self.pos.x != 0 {
             self.pos.x += 1;
         }
-        let table = match highlight {
-            Highlight::Neutral => &self.tables.neu,
-            Highlight::Positive => &self.tables.pos,
-            Highlight::Negative => &self.tables.neg,
-        };
+        let table = self.tables.get(highlight);
         let hex_element = &table[index];
         self.printer.print_styled(self.pos, hex_element.into());
         self.pos.x += 2;
@@ -120,11 +155,7 @@ pub struct VisualPrinter<'a, 'b, 'x> {
 
 impl<'a, 'b, 'x> HexVisitor for VisualPrinter<'a, 'b, 'x> {
     fn byte(&mut self, index: usize, highlight: Highlight) {
-        let table = match highlight {
-            Highlight::Neutral => &self.tables.neu,
-            Highlight::Positive => &self.tables.pos,
-            Highlight::Negative => &self.tables.neg,
-        };
+        let table = self.tables.get(highlight);
         let vis_element = &table[index];
         self.printer.print_styled(self.pos, vis_element.into());
         self.pos.x += vis_element.width();
@@ -18,25 +18,47 @@
 extern crate serde;
 extern crate serde_derive;
 
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, ErrorKind, IsTerminal};
 use std::process::exit;
 
+use crate::byte_reader::TilingByteReader;
+use crate::hex_reader::HexReader;
 use crate::utilities::{exit_reader_open_error, PKG_DESCRIPTION, PKG_NAME, PKG_VERSION};
 use crate::xxv_state::XxvState;
 
 mod utilities;
 mod panic_hook;
+mod search_matcher;
+mod number_search;
 mod file_search;
+mod background_search;
+mod edit_history;
 mod xxv_state;
+mod piece_table;
+mod decompress;
+mod archive;
+mod process_memory;
+mod spool;
 mod byte_reader;
 mod hex_tables;
+mod clipboard;
+mod data_inspector;
 mod hex_reader;
 mod hex_view;
 mod hex_view_printers;
+mod hex_dump;
 mod set_width_dialog;
 mod goto_dialog;
+mod copy_dialog;
 mod open_file_dialog;
 mod switch_file_dialog;
 mod search_dialog;
+mod results_dialog;
+mod mappings_dialog;
+mod archive_dialog;
+mod replace_dialog;
 mod status_bar;
 mod help_text;
 mod xxv_tui;
@@ -46,7 +68,11 @@ fn main() {
 
     let mut args = std::env::args_os();
     args.next(); // The first argument is (most likely) the path to our executable.
-    let file_arg = args.next();
+    let mut file_arg = args.next();
+    if file_arg.is_none() && !stdin().is_terminal() {
+        // Input is piped in, as in `cat file | xv`.
+        file_arg = Some("-".into());
+    }
     
     if let Some(option) = &file_arg {
         if option.eq("-h") || option.eq("--help") {
@@ -61,6 +87,21 @@ fn main() {
             eprintln!("{} {}", PKG_NAME, PKG_VERSION);
             return;
         }
+        
+        if option.eq("--dump") {
+            run_dump(args);
+            return;
+        }
+        
+        if option.eq("--reverse") {
+            run_reverse(args.next());
+            return;
+        }
+        
+        if option.eq("--pid") {
+            run_pid(args.next());
+            return;
+        }
     }
 
     let mut state = XxvState::load();
@@ -84,3 +125,71 @@ fn main() {
         }
     }
 }
+
+fn run_dump(args: std::env::ArgsOs) {
+    let options = match hex_dump::parse_dump_args(args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            eprintln!();
+            eprintln!("For more information, try --help.");
+            exit(64); // EX_USAGE from sysexits.h
+        }
+    };
+    let mut state = XxvState::load();
+    let opened = if options.raw { state.open_reader(&options.file) } else { state.open_decompressed_reader(&options.file) };
+    let mut reader = match opened {
+        Ok(reader) => reader,
+        Err(e) => exit_reader_open_error(e, &options.file),
+    };
+    let stdout = stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match hex_dump::dump(&mut reader, &options, &mut out) {
+        // The output was cut short on purpose, such as by piping it into `head`.
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => (),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit(1);
+        },
+        Ok(()) => (),
+    }
+}
+
+/// Browse the memory of the running process with the given id.
+fn run_pid(pid_arg: Option<OsString>) {
+    let pid = match pid_arg.as_ref().and_then(|pid| pid.to_str()).and_then(|pid| pid.parse::<u32>().ok()) {
+        Some(pid) => pid,
+        None => {
+            eprintln!("Error: --pid needs the id of a running process.");
+            eprintln!();
+            eprintln!("For more information, try --help.");
+            exit(64); // EX_USAGE from sysexits.h
+        }
+    };
+    match TilingByteReader::from_process(pid).and_then(HexReader::new) {
+        Ok(reader) => xxv_tui::run_tui(Some(reader), XxvState::load()),
+        Err(e) => exit_reader_open_error(e, format!("/proc/{}/mem", pid)),
+    }
+}
+
+/// Read a hex dump from the file, or from stdin if there is no file, and write the bytes to stdout.
+fn run_reverse(file_arg: Option<OsString>) {
+    let stdin = stdin();
+    let mut input: Box<dyn BufRead> = match &file_arg {
+        Some(file_name) => match File::open(file_name) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => exit_reader_open_error(e, file_name),
+        },
+        None => Box::new(stdin.lock()),
+    };
+    let stdout = stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match hex_dump::reverse(&mut input, &mut out) {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => (),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit(65); // EX_DATAERR from sysexits.h
+        },
+        Ok(()) => (),
+    }
+}
@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use cursive::event::Key;
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::views::{Dialog, DummyView, EditView, LinearLayout, OnEventView, SelectView, TextView};
+use cursive::Cursive;
+
+use crate::hex_view::HexView;
+use crate::process_memory::Mapping;
+use crate::utilities::get_content;
+use crate::xxv_tui::{OBJ_HEX_VIEW, OBJ_MAPPINGS_FILTER, OBJ_MAPPINGS_LIST};
+
+pub fn open_mappings_dialog(s: &mut Cursive) {
+    let mappings = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        if !v.is_process_memory() {
+            v.show_message("Only process memory has mappings. Open it with --pid.".to_string());
+        }
+        v.mappings()
+    }).unwrap();
+    if mappings.is_empty() {
+        return;
+    }
+    let count_text = format!("{} mappings.", mappings.len());
+    let rows = Rc::new(mapping_rows(&mappings));
+
+    let filter_rows = rows.clone();
+    let filter_field = EditView::new()
+        .on_edit(move |s, _, _| fill_list(s, &filter_rows))
+        .with_name(OBJ_MAPPINGS_FILTER)
+        .min_width(30);
+
+    let list: SelectView<u64> = SelectView::new().on_submit(go_to_mapping);
+
+    let layout = LinearLayout::vertical()
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("Filter: "))
+            .child(filter_field))
+        .child(DummyView)
+        .child(list.with_name(OBJ_MAPPINGS_LIST).scrollable().max_height(20))
+        .child(DummyView)
+        .child(TextView::new(count_text));
+
+    let dialog = Dialog::around(layout)
+        .dismiss_button("Close")
+        .title("Memory mappings");
+
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(esc_view);
+    fill_list(s, &rows);
+}
+
+/// Label each mapping with its address range, permissions, size, and backing file, and pair it with its offset.
+fn mapping_rows(mappings: &[Mapping]) -> Vec<(String, u64)> {
+    let address_width = format!("{:X}", mappings.iter().map(|m| m.end).max().unwrap_or(0)).len();
+    mappings.iter().map(|mapping| {
+        let label = format!(
+            "{:0aw$X}-{:0aw$X} {} {:>9} KiB  {}",
+            mapping.start, mapping.end, mapping.permissions, mapping.len() / 1024, mapping.path,
+            aw = address_width);
+        (label, mapping.offset)
+    }).collect()
+}
+
+fn fill_list(s: &mut Cursive, rows: &[(String, u64)]) {
+    let filter = s.call_on_name(OBJ_MAPPINGS_FILTER, get_content).unwrap().to_lowercase();
+    s.call_on_name(OBJ_MAPPINGS_LIST, |v: &mut SelectView<u64>| {
+        v.clear();
+        for (label, offset) in rows {
+            if filter.is_empty() || label.to_lowercase().contains(&filter) {
+                v.add_item(label.clone(), *offset);
+            }
+        }
+    });
+}
+
+fn go_to_mapping(s: &mut Cursive, offset: &u64) {
+    s.pop_layer();
+    s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.go_to_offset(*offset));
+}
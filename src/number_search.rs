@@ -0,0 +1,261 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::search_matcher::{AlternativesMatcher, Matcher};
+use crate::utilities::parse_number;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NumberType {
+    U16,
+    U32,
+    U64,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl NumberType {
+    pub const ALL: [NumberType; 8] = [
+        NumberType::U16,
+        NumberType::U32,
+        NumberType::U64,
+        NumberType::I16,
+        NumberType::I32,
+        NumberType::I64,
+        NumberType::F32,
+        NumberType::F64,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NumberType::U16 => "u16",
+            NumberType::U32 => "u32",
+            NumberType::U64 => "u64",
+            NumberType::I16 => "i16",
+            NumberType::I32 => "i32",
+            NumberType::I64 => "i64",
+            NumberType::F32 => "f32",
+            NumberType::F64 => "f64",
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            NumberType::U16 | NumberType::I16 => 2,
+            NumberType::U32 | NumberType::I32 | NumberType::F32 => 4,
+            NumberType::U64 | NumberType::I64 | NumberType::F64 => 8,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumberType::F32 | NumberType::F64)
+    }
+
+    fn range(&self) -> (i128, i128) {
+        match self {
+            NumberType::U16 => (0, i128::from(u16::MAX)),
+            NumberType::U32 => (0, i128::from(u32::MAX)),
+            NumberType::U64 => (0, i128::from(u64::MAX)),
+            NumberType::I16 => (i128::from(i16::MIN), i128::from(i16::MAX)),
+            NumberType::I32 => (i128::from(i32::MIN), i128::from(i32::MAX)),
+            NumberType::I64 => (i128::from(i64::MIN), i128::from(i64::MAX)),
+            NumberType::F32 | NumberType::F64 => (0, 0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+    Both,
+}
+
+impl ByteOrder {
+    pub const ALL: [ByteOrder; 3] = [ByteOrder::Little, ByteOrder::Big, ByteOrder::Both];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteOrder::Little => "Little endian",
+            ByteOrder::Big => "Big endian",
+            ByteOrder::Both => "Both",
+        }
+    }
+
+    fn includes_little(&self) -> bool {
+        *self != ByteOrder::Big
+    }
+
+    fn includes_big(&self) -> bool {
+        *self != ByteOrder::Little
+    }
+}
+
+/// Build a matcher that finds the number, encoded as the given type, in the given byte orders.
+/// Integers can be written in decimal, hex with a `0x` prefix, or octal with a `0` prefix, and can be negative.
+/// Floats match any value that is at most the tolerance away from the number. An empty tolerance means zero.
+pub fn number_matcher(number: &str, number_type: NumberType, order: ByteOrder, tolerance: &str) -> Result<Box<dyn Matcher>> {
+    let number = number.trim();
+    if number_type.is_float() {
+        let mut value = parse_float(number)?;
+        if number_type == NumberType::F32 {
+            // Most decimals, like 1.1, cannot be stored exactly, so compare with the f32 closest to the query.
+            value = f64::from(value as f32);
+        }
+        let tolerance = if tolerance.trim().is_empty() { 0.0 } else { parse_float(tolerance.trim())?.abs() };
+        return Ok(Box::new(FloatMatcher { width: number_type.width(), value, tolerance, order }));
+    }
+
+    let value = parse_integer(number)?;
+    let (min, max) = number_type.range();
+    if value < min || value > max {
+        let message = format!("{} does not fit in {}.", number, number_type.name());
+        return Err(Error::new(ErrorKind::InvalidInput, message));
+    }
+    // Two's complement, truncated to the width of the type.
+    let little_endian = &value.to_le_bytes()[..number_type.width()];
+    let mut encodings = Vec::new();
+    if order.includes_little() {
+        encodings.push(little_endian.to_vec());
+    }
+    if order.includes_big() {
+        encodings.push(little_endian.iter().rev().copied().collect());
+    }
+    Ok(Box::new(AlternativesMatcher::new(&encodings)))
+}
+
+fn parse_integer(number: &str) -> Result<i128> {
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, number),
+    };
+    let magnitude = parse_number(digits)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Not a number: {} ({})", number, e)))?;
+    let magnitude = i128::from(magnitude);
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+fn parse_float(number: &str) -> Result<f64> {
+    number.parse().map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Not a number: {} ({})", number, e)))
+}
+
+/// Matches floats that are close to a value, by decoding the bytes at every offset.
+struct FloatMatcher {
+    width: usize,
+    value: f64,
+    tolerance: f64,
+    order: ByteOrder,
+}
+
+impl FloatMatcher {
+    fn decode(&self, bytes: &[u8], little_endian: bool) -> f64 {
+        let mut buf = [0; 8];
+        buf[..self.width].copy_from_slice(bytes);
+        if !little_endian {
+            buf[..self.width].reverse();
+        }
+        if self.width == 4 {
+            f64::from(f32::from_le_bytes(<[u8; 4]>::try_from(&buf[..4]).unwrap()))
+        } else {
+            f64::from_le_bytes(buf)
+        }
+    }
+
+    fn is_close(&self, found: f64) -> bool {
+        (found - self.value).abs() <= self.tolerance
+    }
+}
+
+impl Matcher for FloatMatcher {
+    fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+        let end = haystack.len().checked_sub(self.width)?;
+        (start..=end)
+            .find(|&p| {
+                let bytes = &haystack[p..p + self.width];
+                (self.order.includes_little() && self.is_close(self.decode(bytes, true)))
+                    || (self.order.includes_big() && self.is_close(self.decode(bytes, false)))
+            })
+            .map(|p| (p, self.width))
+    }
+
+    fn max_match_length(&self) -> usize {
+        self.width
+    }
+
+    fn is_fixed_length(&self) -> bool {
+        true
+    }
+
+    fn next_start(&self, start: usize, _length: usize) -> usize {
+        start + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_all(matcher: &dyn Matcher, haystack: &[u8]) -> Vec<usize> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while let Some((s, len)) = matcher.find_at(haystack, start) {
+            matches.push(s);
+            start = matcher.next_start(s, len);
+        }
+        matches
+    }
+
+    #[test]
+    fn integers_in_both_byte_orders() {
+        let haystack = b"\x34\x12\x00\x00\x12\x34\x00\x12\x34";
+        let matcher = number_matcher("0x1234", NumberType::U16, ByteOrder::Little, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), haystack), vec![0]);
+        let matcher = number_matcher("4660", NumberType::U16, ByteOrder::Big, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), haystack), vec![4, 7]);
+        let matcher = number_matcher("4660", NumberType::U16, ByteOrder::Both, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), haystack), vec![0, 4, 7]);
+        let matcher = number_matcher("4660", NumberType::U32, ByteOrder::Both, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), haystack), vec![0, 2]);
+    }
+
+    #[test]
+    fn negative_integers() {
+        let matcher = number_matcher("-2", NumberType::I32, ByteOrder::Little, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), b"\x00\xFE\xFF\xFF\xFF\x00"), vec![1]);
+    }
+
+    #[test]
+    fn integers_out_of_range() {
+        assert!(number_matcher("65536", NumberType::U16, ByteOrder::Both, "").is_err());
+        assert!(number_matcher("-1", NumberType::U64, ByteOrder::Both, "").is_err());
+        assert!(number_matcher("-32768", NumberType::I16, ByteOrder::Both, "").is_ok());
+        assert!(number_matcher("12x", NumberType::I16, ByteOrder::Both, "").is_err());
+    }
+
+    #[test]
+    fn floats_with_tolerance() {
+        let mut haystack = Vec::new();
+        haystack.extend_from_slice(&1.5f32.to_le_bytes());
+        haystack.extend_from_slice(&1.52f32.to_be_bytes());
+        haystack.extend_from_slice(&3.0f64.to_le_bytes());
+        let matcher = number_matcher("1.5", NumberType::F32, ByteOrder::Both, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), &haystack), vec![0]);
+        let matcher = number_matcher("1.5", NumberType::F32, ByteOrder::Both, "0.05").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), &haystack), vec![0, 4]);
+        let matcher = number_matcher("3", NumberType::F64, ByteOrder::Little, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), &haystack), vec![8]);
+    }
+
+    #[test]
+    fn f32_values_that_are_not_exact() {
+        let mut haystack = Vec::new();
+        haystack.extend_from_slice(&1.1f32.to_le_bytes());
+        haystack.extend_from_slice(&0.1f32.to_be_bytes());
+        let matcher = number_matcher("1.1", NumberType::F32, ByteOrder::Little, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), &haystack), vec![0]);
+        let matcher = number_matcher("0.1", NumberType::F32, ByteOrder::Big, "").unwrap();
+        assert_eq!(find_all(matcher.as_ref(), &haystack), vec![4]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::hex_view::HexView;
 use crate::xxv_state::XxvState;
-use crate::xxv_tui::{ShowError, OBJ_CURRENT_DIR, OBJ_DIR_SELECTOR, OBJ_FILE_SELECTOR, OBJ_HEX_VIEW};
+use crate::xxv_tui::{confirm_unsaved_changes, offer_decompression, watch_input, ShowError, OBJ_CURRENT_DIR, OBJ_DIR_SELECTOR, OBJ_FILE_SELECTOR, OBJ_HEX_VIEW};
 use cursive::event::Key;
 use cursive::theme::Effect;
 use cursive::traits::{Resizable, Nameable};
@@ -135,24 +135,26 @@ fn do_open_file(s: &mut Cursive) {
         .unwrap();
     s.pop_layer();
     if let Some(rc_file) = file_selector.selection() {
-        let file_name = rc_file.as_ref();
-        let current_file = s
-            .call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.get_reader_state())
-            .unwrap();
-        if let Some(reader_result) = s.with_user_data(|state: &mut XxvState| {
-            let path = state.resolve_path(file_name);
-            state.close_reader(current_file);
-            state.open_reader(path)
-        }) {
-            match reader_result {
-                Ok(reader) => s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
-                    view.switch_reader(reader);
-                }),
-                Err(error) => {
-                    s.show_error(error);
-                    None
-                }
-            };
+        confirm_unsaved_changes(s, move |s| open_file(s, &rc_file));
+    }
+}
+
+fn open_file(s: &mut Cursive, file_name: &OsStr) {
+    let current_file = s
+        .call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.get_reader_state())
+        .unwrap();
+    if let Some(reader_result) = s.with_user_data(|state: &mut XxvState| {
+        let path = state.resolve_path(file_name);
+        state.close_reader(current_file);
+        state.open_reader(path)
+    }) {
+        match reader_result {
+            Ok(reader) => {
+                s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.switch_reader(reader));
+                watch_input(s);
+                offer_decompression(s, None);
+            },
+            Err(error) => s.show_error(error),
         }
     }
 }
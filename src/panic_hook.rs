@@ -3,7 +3,7 @@ use std::fmt::Write as StrWrite;
 use std::fs::{create_dir_all, OpenOptions, rename};
 use std::io::{stderr, Write as IoWrite};
 use std::panic;
-use std::panic::PanicInfo;
+use std::panic::PanicHookInfo;
 use std::path::PathBuf;
 
 use backtrace::Backtrace;
@@ -16,7 +16,7 @@ use crate::utilities;
 pub const CRASH_LOG_FILE_NAME: &str = "crash.log";
 
 pub fn install() {
-    panic::set_hook(Box::new(|info: &PanicInfo| {
+    panic::set_hook(Box::new(|info: &PanicHookInfo| {
         if let Err(e) = report_crash(info) {
             eprintln!("Panic hook failure: {}.", e);
         }
@@ -39,7 +39,7 @@ pub fn archive_last_crash() -> Option<PathBuf> {
     None
 }
 
-fn report_crash(info: &PanicInfo) -> std::fmt::Result {
+fn report_crash(info: &PanicHookInfo) -> std::fmt::Result {
     let mut msg = String::new();
 
     writeln!(msg)?;
@@ -0,0 +1,306 @@
+use std::convert::TryFrom;
+use std::io::Result;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Source {
+    Original,
+    Added,
+}
+
+/// A contiguous run of bytes, taken either from the original file, or from the buffer of added bytes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Piece {
+    pub source: Source,
+    pub start: u64,
+    pub length: u64,
+}
+
+/// The edited layout of a file, as a sequence of pieces.
+///
+/// Edits only ever split and rearrange pieces, and append to the buffer of added bytes,
+/// so inserting and deleting bytes is cheap regardless of the size of the file.
+/// The original file is never touched.
+#[derive(Clone, Debug)]
+pub struct PieceTable {
+    pieces: Vec<Piece>,
+    starts: Vec<u64>,
+    added: Vec<u8>,
+    length: u64,
+    original_length: u64,
+}
+
+impl PieceTable {
+    pub fn new(original_length: u64) -> PieceTable {
+        let mut table = PieceTable {
+            pieces: Vec::new(),
+            starts: Vec::new(),
+            added: Vec::new(),
+            length: 0,
+            original_length,
+        };
+        if original_length > 0 {
+            table.pieces.push(Piece { source: Source::Original, start: 0, length: original_length });
+        }
+        table.update_starts();
+        table
+    }
+
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    pub fn added_bytes(&self, piece: &Piece) -> &[u8] {
+        let start = usize::try_from(piece.start).unwrap();
+        let end = usize::try_from(piece.start + piece.length).unwrap();
+        &self.added[start..end]
+    }
+
+    /// True if there are no edits, or if all edits are overwrites that leave the original bytes in place.
+    pub fn is_in_place(&self) -> bool {
+        self.length == self.original_length && self.pieces.iter().zip(self.starts.iter())
+            .all(|(piece, &start)| piece.source == Source::Added || piece.start == start)
+    }
+
+    pub fn has_edits(&self) -> bool {
+        self.length != self.original_length || !self.pieces.iter().zip(self.starts.iter())
+            .all(|(piece, &start)| piece.source == Source::Original && piece.start == start)
+    }
+
+    /// Replace `remove_length` bytes at the given offset, with the given bytes.
+    /// This covers overwriting, inserting and deleting bytes.
+    pub fn replace(&mut self, offset: u64, remove_length: u64, bytes: &[u8]) {
+        let offset = offset.min(self.length);
+        let remove_end = (offset + remove_length).min(self.length);
+        let first = self.split_at(offset);
+        let last = self.split_at(remove_end);
+        let mut replacement = Vec::with_capacity(1);
+        if !bytes.is_empty() {
+            let added_start = u64::try_from(self.added.len()).unwrap();
+            self.added.extend_from_slice(bytes);
+            replacement.push(Piece {
+                source: Source::Added,
+                start: added_start,
+                length: u64::try_from(bytes.len()).unwrap(),
+            });
+        }
+        self.pieces.splice(first..last, replacement);
+        self.merge_around(first);
+        self.update_starts();
+    }
+
+    /// Add bytes that were appended to the original file, to the end of the edited contents.
+    pub fn append_original(&mut self, original_length: u64) {
+        if original_length <= self.original_length {
+            return;
+        }
+        let index = self.pieces.len();
+        self.pieces.push(Piece {
+            source: Source::Original,
+            start: self.original_length,
+            length: original_length - self.original_length,
+        });
+        self.original_length = original_length;
+        self.merge_around(index);
+        self.update_starts();
+    }
+
+    pub fn is_added(&self, offset: u64) -> bool {
+        match self.index_of(offset) {
+            Some(index) => self.pieces[index].source == Source::Added,
+            None => false,
+        }
+    }
+
+    /// Read the edited bytes at the given offset into the buffer.
+    /// Bytes from the original file are read with the given function.
+    pub fn read<F>(&self, offset: u64, buf: &mut [u8], mut read_original: F) -> Result<usize>
+        where F: FnMut(u64, &mut [u8]) -> Result<usize> {
+        let mut filled = 0;
+        let mut index = match self.index_of(offset) {
+            Some(index) => index,
+            None => return Ok(0),
+        };
+        let mut piece_offset = offset - self.starts[index];
+        while filled < buf.len() && index < self.pieces.len() {
+            let piece = &self.pieces[index];
+            let available = piece.length - piece_offset;
+            let wanted = u64::try_from(buf.len() - filled).unwrap().min(available);
+            let wanted_usize = usize::try_from(wanted).unwrap();
+            let dst = &mut buf[filled..filled + wanted_usize];
+            match piece.source {
+                Source::Added => {
+                    let start = usize::try_from(piece.start + piece_offset).unwrap();
+                    dst.copy_from_slice(&self.added[start..start + wanted_usize]);
+                    filled += wanted_usize;
+                },
+                Source::Original => {
+                    let bytes_read = read_original(piece.start + piece_offset, dst)?;
+                    filled += bytes_read;
+                    if bytes_read < wanted_usize {
+                        // The original file is shorter than expected; it was probably truncated.
+                        break;
+                    }
+                },
+            }
+            index += 1;
+            piece_offset = 0;
+        }
+        Ok(filled)
+    }
+
+    fn index_of(&self, offset: u64) -> Option<usize> {
+        if offset >= self.length {
+            return None;
+        }
+        match self.starts.binary_search(&offset) {
+            Ok(index) => Some(index),
+            Err(index) => Some(index - 1),
+        }
+    }
+
+    /// Make sure a piece begins at the given offset, and return its index.
+    fn split_at(&mut self, offset: u64) -> usize {
+        let index = match self.index_of(offset) {
+            Some(index) => index,
+            None => return self.pieces.len(),
+        };
+        let piece_offset = offset - self.starts[index];
+        if piece_offset == 0 {
+            return index;
+        }
+        let piece = self.pieces[index];
+        self.pieces[index].length = piece_offset;
+        self.pieces.insert(index + 1, Piece {
+            source: piece.source,
+            start: piece.start + piece_offset,
+            length: piece.length - piece_offset,
+        });
+        self.starts.insert(index + 1, offset);
+        index + 1
+    }
+
+    fn merge_around(&mut self, index: usize) {
+        let mut i = index.saturating_sub(1);
+        let end = (index + 1).min(self.pieces.len());
+        while i < end && i + 1 < self.pieces.len() {
+            let a = self.pieces[i];
+            let b = self.pieces[i + 1];
+            if a.source == b.source && a.start + a.length == b.start {
+                self.pieces[i].length += b.length;
+                self.pieces.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn update_starts(&mut self) {
+        self.starts.clear();
+        let mut start = 0;
+        for piece in &self.pieces {
+            self.starts.push(start);
+            start += piece.length;
+        }
+        self.length = start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &[u8] = b"0123456789";
+
+    fn read_all(table: &PieceTable) -> Vec<u8> {
+        let mut buf = vec![0; usize::try_from(table.len()).unwrap()];
+        let read = table.read(0, &mut buf, |offset, dst| {
+            let start = usize::try_from(offset).unwrap();
+            let end = (start + dst.len()).min(ORIGINAL.len());
+            dst[..end - start].copy_from_slice(&ORIGINAL[start..end]);
+            Ok(end - start)
+        }).unwrap();
+        buf.truncate(read);
+        buf
+    }
+
+    #[test]
+    fn unedited_table() {
+        let table = PieceTable::new(10);
+        assert_eq!(table.len(), 10);
+        assert_eq!(read_all(&table), b"0123456789");
+        assert!(!table.has_edits());
+        assert!(table.is_in_place());
+    }
+
+    #[test]
+    fn appending_to_the_original() {
+        let mut table = PieceTable::new(6);
+        table.replace(2, 1, b"x");
+        table.append_original(10);
+        assert_eq!(read_all(&table), b"01x3456789");
+        assert_eq!(table.pieces().len(), 3);
+        assert!(table.is_in_place());
+        table.append_original(8);
+        assert_eq!(table.len(), 10);
+    }
+
+    #[test]
+    fn overwriting() {
+        let mut table = PieceTable::new(10);
+        table.replace(3, 1, b"a");
+        table.replace(4, 1, b"b");
+        assert_eq!(read_all(&table), b"012ab56789");
+        assert_eq!(table.pieces().len(), 3);
+        assert!(table.is_added(3));
+        assert!(table.is_added(4));
+        assert!(!table.is_added(5));
+        assert!(table.has_edits());
+        assert!(table.is_in_place());
+    }
+
+    #[test]
+    fn inserting() {
+        let mut table = PieceTable::new(10);
+        table.replace(0, 0, b"ab");
+        table.replace(12, 0, b"yz");
+        table.replace(7, 0, b"-");
+        assert_eq!(read_all(&table), b"ab01234-56789yz");
+        assert_eq!(table.len(), 15);
+        assert!(!table.is_in_place());
+    }
+
+    #[test]
+    fn deleting() {
+        let mut table = PieceTable::new(10);
+        table.replace(2, 3, b"");
+        assert_eq!(read_all(&table), b"0156789");
+        table.replace(0, 1, b"");
+        table.replace(5, 10, b"");
+        assert_eq!(read_all(&table), b"15678");
+        assert!(!table.is_in_place());
+        
+        let mut table = PieceTable::new(10);
+        table.replace(8, 2, b"");
+        assert_eq!(read_all(&table), b"01234567");
+        assert!(table.has_edits());
+        assert!(!table.is_in_place());
+    }
+
+    #[test]
+    fn reading_across_pieces_from_the_middle() {
+        let mut table = PieceTable::new(10);
+        table.replace(5, 0, b"abc");
+        let mut buf = [0; 4];
+        let read = table.read(3, &mut buf, |offset, dst| {
+            let start = usize::try_from(offset).unwrap();
+            dst.copy_from_slice(&ORIGINAL[start..start + dst.len()]);
+            Ok(dst.len())
+        }).unwrap();
+        assert_eq!(read, 4);
+        assert_eq!(&buf, b"34ab");
+    }
+}
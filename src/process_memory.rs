@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Unreadable memory is found one page at a time.
+const PAGE_SIZE: u64 = 4096;
+
+/// A range of virtual memory that is mapped into a process, as listed in `/proc/<pid>/maps`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: String,
+    /// The backing file, or a name like `[heap]` or `[stack]`. Empty for anonymous memory.
+    pub path: String,
+    /// Where the mapping starts in the reader, which lays the mappings out one after another.
+    pub offset: u64,
+}
+
+impl Mapping {
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Mappings without read permission, like guard pages, are not read at all.
+    pub fn is_readable(&self) -> bool {
+        self.permissions.starts_with('r')
+    }
+}
+
+/// The memory of a running process, read through `/proc/<pid>/mem`.
+/// Only the mapped ranges are exposed, so the offsets are not the same as the virtual addresses.
+#[derive(Debug)]
+pub struct ProcessMemory {
+    pid: u32,
+    name: String,
+    mappings: Vec<Mapping>,
+    /// The ranges of pages in readable mappings that could not be read, from their start to their end address.
+    /// Neighbouring ranges are merged, so that a large unreadable area takes up a single entry.
+    unreadable: Mutex<BTreeMap<u64, u64>>,
+}
+
+impl ProcessMemory {
+    pub fn open(pid: u32) -> Result<ProcessMemory> {
+        let maps = fs::read_to_string(format!("/proc/{}/maps", pid))?;
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid)).unwrap_or_default();
+        let mut memory = ProcessMemory::parse(pid, &maps)?;
+        memory.name = name.trim_end().to_string();
+        Ok(memory)
+    }
+
+    fn parse(pid: u32, maps: &str) -> Result<ProcessMemory> {
+        let mut mappings = Vec::new();
+        let mut offset = 0;
+        for line in maps.lines().filter(|line| !line.is_empty()) {
+            let mapping = parse_mapping(line, offset)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unexpected memory mapping: {}", line)))?;
+            offset += mapping.len();
+            mappings.push(mapping);
+        }
+        Ok(ProcessMemory {
+            pid,
+            name: String::new(),
+            mappings,
+            unreadable: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(format!("/proc/{}/mem", self.pid))
+    }
+
+    pub fn display_name(&self) -> String {
+        if self.name.is_empty() {
+            format!("pid {}", self.pid)
+        } else {
+            format!("{} (pid {})", self.name, self.pid)
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn len(&self) -> u64 {
+        self.mappings.last().map_or(0, |mapping| mapping.offset + mapping.len())
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    fn mapping_at(&self, offset: u64) -> Option<&Mapping> {
+        let index = self.mappings.partition_point(|mapping| mapping.offset + mapping.len() <= offset);
+        self.mappings.get(index)
+    }
+
+    /// The virtual address of the byte at the given offset.
+    pub fn address_at(&self, offset: u64) -> Option<u64> {
+        self.mapping_at(offset).map(|mapping| mapping.start + (offset - mapping.offset))
+    }
+
+    pub fn max_address(&self) -> u64 {
+        self.mappings.iter().map(|mapping| mapping.end).max().unwrap_or(0)
+    }
+
+    pub fn is_unreadable(&self, offset: u64) -> bool {
+        match self.mapping_at(offset) {
+            Some(mapping) if !mapping.is_readable() => true,
+            Some(mapping) => unreadable_range_end(&self.unreadable.lock().unwrap(), mapping.start + (offset - mapping.offset)).is_some(),
+            None => false,
+        }
+    }
+
+    /// Read the memory at the given offset. Pages that cannot be read are filled with zeros, and remembered as unreadable.
+    pub fn read_at(&self, file: &mut File, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let offset = offset + u64::try_from(filled).unwrap();
+            let mapping = match self.mapping_at(offset) {
+                Some(mapping) => mapping,
+                None => break,
+            };
+            let left_in_mapping = mapping.offset + mapping.len() - offset;
+            let length = usize::try_from(left_in_mapping).unwrap_or(usize::MAX).min(buf.len() - filled);
+            let address = mapping.start + (offset - mapping.offset);
+            if mapping.is_readable() {
+                self.read_pages(file, address, &mut buf[filled..filled + length]);
+            } else {
+                buf[filled..filled + length].iter_mut().for_each(|b| *b = 0);
+            }
+            filled += length;
+        }
+        Ok(filled)
+    }
+
+    fn read_pages(&self, file: &mut File, address: u64, buf: &mut [u8]) {
+        let end = address + u64::try_from(buf.len()).unwrap();
+        let known_unreadable = self.unreadable.lock().unwrap().range(..end).next_back().is_some_and(|(_, &e)| e > address);
+        if !known_unreadable && read_fully(file, address, buf) {
+            return;
+        }
+        // Some of the pages cannot be read, so find out which, and read the rest.
+        // Pages that are already known to be unreadable are not read again.
+        let mut filled = 0;
+        while filled < buf.len() {
+            let page_address = address + u64::try_from(filled).unwrap();
+            let page_start = page_address - page_address % PAGE_SIZE;
+            let known_end = unreadable_range_end(&self.unreadable.lock().unwrap(), page_address);
+            let page_end = known_end.unwrap_or(page_start + PAGE_SIZE).min(end);
+            let length = usize::try_from(page_end - page_address).unwrap();
+            let page = &mut buf[filled..filled + length];
+            if known_end.is_some() {
+                page.iter_mut().for_each(|b| *b = 0);
+            } else if !read_fully(file, page_address, page) {
+                page.iter_mut().for_each(|b| *b = 0);
+                add_unreadable_range(&mut self.unreadable.lock().unwrap(), page_start, page_start + PAGE_SIZE);
+            }
+            filled += length;
+        }
+    }
+}
+
+/// The end of the unreadable range that contains the address, if there is one.
+fn unreadable_range_end(ranges: &BTreeMap<u64, u64>, address: u64) -> Option<u64> {
+    ranges.range(..=address).next_back().map(|(_, &end)| end).filter(|&end| end > address)
+}
+
+/// Add a range of unreadable pages, and merge it with the ranges that overlap or touch it.
+fn add_unreadable_range(ranges: &mut BTreeMap<u64, u64>, mut start: u64, mut end: u64) {
+    if let Some((&previous_start, &previous_end)) = ranges.range(..=start).next_back() {
+        if previous_end >= start {
+            start = previous_start;
+            end = end.max(previous_end);
+        }
+    }
+    while let Some((&next_start, &next_end)) = ranges.range(start..).next() {
+        if next_start > end {
+            break;
+        }
+        end = end.max(next_end);
+        ranges.remove(&next_start);
+    }
+    ranges.insert(start, end);
+}
+
+fn read_fully(file: &mut File, address: u64, buf: &mut [u8]) -> bool {
+    file.seek(SeekFrom::Start(address)).is_ok() && file.read_exact(buf).is_ok()
+}
+
+/// Parse a line like `7f12a000-7f12b000 r-xp 00000000 08:01 1234   /usr/lib/libc.so.6`.
+fn parse_mapping(line: &str, offset: u64) -> Option<Mapping> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let permissions = fields.next()?.to_string();
+    let path = fields.nth(3).unwrap_or("").trim_start().to_string();
+    let start = u64::from_str_radix(start, 16).ok()?;
+    let end = u64::from_str_radix(end, 16).ok()?;
+    if end < start {
+        return None;
+    }
+    Some(Mapping { start, end, permissions, path, offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = "\
+55d0c8a00000-55d0c8a02000 r--p 00000000 08:01 1234                       /usr/bin/cat
+55d0c8a02000-55d0c8a03000 r-xp 00002000 08:01 1234                       /usr/bin/cat
+55d0ca1b6000-55d0ca1d7000 rw-p 00000000 00:00 0                          [heap]
+7f7a2c000000-7f7a2c001000 ---p 00000000 00:00 0
+ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsyscall]
+";
+
+    #[test]
+    fn parsing_memory_mappings() {
+        let memory = ProcessMemory::parse(42, MAPS).unwrap();
+        let mappings = memory.mappings();
+        assert_eq!(mappings.len(), 5);
+        assert_eq!(mappings[0], Mapping {
+            start: 0x55d0c8a00000,
+            end: 0x55d0c8a02000,
+            permissions: "r--p".to_string(),
+            path: "/usr/bin/cat".to_string(),
+            offset: 0,
+        });
+        assert_eq!(mappings[1].offset, 0x2000);
+        assert_eq!(mappings[2].path, "[heap]");
+        assert_eq!(mappings[3].path, "");
+        assert_eq!(mappings[3].permissions, "---p");
+        assert_eq!(memory.len(), 0x2000 + 0x1000 + 0x21000 + 0x1000 + 0x1000);
+        assert_eq!(memory.max_address(), 0xffffffffff601000);
+        assert_eq!(memory.path(), PathBuf::from("/proc/42/mem"));
+        assert!(ProcessMemory::parse(42, "not a mapping").is_err());
+    }
+
+    #[test]
+    fn mapping_offsets_to_addresses() {
+        let memory = ProcessMemory::parse(42, MAPS).unwrap();
+        assert_eq!(memory.address_at(0), Some(0x55d0c8a00000));
+        assert_eq!(memory.address_at(0x1fff), Some(0x55d0c8a01fff));
+        assert_eq!(memory.address_at(0x2000), Some(0x55d0c8a02000));
+        assert_eq!(memory.address_at(0x3000), Some(0x55d0ca1b6000));
+        assert_eq!(memory.address_at(memory.len() - 1), Some(0xffffffffff600fff));
+        assert_eq!(memory.address_at(memory.len()), None);
+    }
+
+    #[test]
+    fn unreadable_ranges_are_merged() {
+        let mut ranges = BTreeMap::new();
+        add_unreadable_range(&mut ranges, 0x3000, 0x4000);
+        add_unreadable_range(&mut ranges, 0x1000, 0x2000);
+        add_unreadable_range(&mut ranges, 0x4000, 0x5000);
+        assert_eq!(ranges.iter().map(|(&s, &e)| (s, e)).collect::<Vec<_>>(), vec![(0x1000, 0x2000), (0x3000, 0x5000)]);
+        add_unreadable_range(&mut ranges, 0x2000, 0x3000);
+        assert_eq!(ranges.iter().map(|(&s, &e)| (s, e)).collect::<Vec<_>>(), vec![(0x1000, 0x5000)]);
+        assert_eq!(unreadable_range_end(&ranges, 0x1000), Some(0x5000));
+        assert_eq!(unreadable_range_end(&ranges, 0x4fff), Some(0x5000));
+        assert_eq!(unreadable_range_end(&ranges, 0x5000), None);
+        assert_eq!(unreadable_range_end(&ranges, 0xfff), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reading_unreadable_memory() {
+        // Nothing can be mapped at the lowest addresses, so reading them fails.
+        let maps = "1000-4000 r--p 00000000 00:00 0\n7f7a2c000000-7f7a2c002000 ---p 00000000 00:00 0\n";
+        let memory = ProcessMemory::parse(std::process::id(), maps).unwrap();
+        let mut file = File::open(memory.path()).unwrap();
+        let mut buf = vec![0xFF; 0x5000];
+        assert_eq!(memory.read_at(&mut file, 0, &mut buf).unwrap(), 0x5000);
+        assert!(buf.iter().all(|&b| b == 0));
+        assert!(memory.is_unreadable(0));
+        assert!(memory.is_unreadable(0x2fff));
+        // The mapping without read permission is not read, so only the other mapping is remembered as unreadable.
+        assert!(memory.is_unreadable(0x3000));
+        assert!(memory.is_unreadable(0x4fff));
+        assert_eq!(memory.unreadable.lock().unwrap().iter().map(|(&s, &e)| (s, e)).collect::<Vec<_>>(), vec![(0x1000, 0x4000)]);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reading_own_memory() {
+        static MARKER: [u8; 16] = *b"xv memory marker";
+        let memory = ProcessMemory::open(std::process::id()).unwrap();
+        let address = u64::try_from(MARKER.as_ptr() as usize).unwrap();
+        let mapping = memory.mappings().iter().find(|m| m.start <= address && address < m.end).unwrap();
+        let offset = mapping.offset + (address - mapping.start);
+
+        let mut file = File::open(memory.path()).unwrap();
+        let mut buf = [0; 16];
+        assert_eq!(memory.read_at(&mut file, offset, &mut buf).unwrap(), 16);
+        assert_eq!(&buf, &MARKER);
+        assert!(!memory.is_unreadable(offset));
+    }
+}
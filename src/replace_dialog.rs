@@ -0,0 +1,177 @@
+use std::cell::Cell;
+use std::ffi::OsString;
+use std::path::Path;
+use std::rc::Rc;
+
+use cursive::event::Key;
+use cursive::traits::{Nameable, Resizable};
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, TextView};
+use cursive::Cursive;
+
+use crate::hex_tables::BYTE_RENDER;
+use crate::hex_view::HexView;
+use crate::utilities::get_content;
+use crate::xxv_tui::{ShowError, OBJ_HEX_VIEW, OBJ_REPLACE_STATUS, OBJ_SAVE_COPY_PATH};
+
+/// The number of replacement bytes that are shown in the dialog.
+const PREVIEW_LENGTH: usize = 16;
+
+/// Open the replace dialog, if a search with a replacement has just finished.
+pub fn open_pending_replace_dialog(s: &mut Cursive) {
+    let pending = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        v.take_pending_replacement().map(|bytes| (bytes, v.search_matches().len()))
+    }).flatten();
+    if let Some((bytes, match_count)) = pending {
+        if match_count == 0 {
+            s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.show_message("Nothing to replace".to_string()));
+        } else {
+            open_replace_dialog(s, bytes, match_count);
+        }
+    }
+}
+
+fn open_replace_dialog(s: &mut Cursive, bytes: Vec<u8>, match_count: usize) {
+    let rendered: Vec<&str> = bytes.iter().take(PREVIEW_LENGTH).map(|&b| BYTE_RENDER[usize::from(b)]).collect();
+    let ellipsis = if bytes.len() > PREVIEW_LENGTH { " ..." } else { "" };
+    let text = format!("Replace with {} bytes:\n{}{}", bytes.len(), rendered.join(" "), ellipsis);
+
+    let bytes = Rc::new(bytes);
+    let replaced = Rc::new(Cell::new(0));
+    let (one_bytes, one_replaced) = (bytes.clone(), replaced.clone());
+    let all_bytes = bytes;
+    let (done_replaced, esc_replaced) = (replaced.clone(), replaced);
+
+    let layout = LinearLayout::vertical()
+        .child(TextView::new(format!("{} matches found.", match_count)).with_name(OBJ_REPLACE_STATUS))
+        .child(TextView::new(text));
+
+    let dialog = Dialog::around(layout)
+        .title("Replace")
+        .button("Replace one", move |s| replace_one(s, &one_bytes, &one_replaced))
+        .button("Replace all", move |s| replace_all(s, &all_bytes))
+        .button("Done", move |s| finish(s, done_replaced.get()))
+        .min_width(40);
+
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, move |s| finish(s, esc_replaced.get()));
+
+    s.add_layer(esc_view);
+}
+
+fn replace_one(s: &mut Cursive, bytes: &[u8], replaced: &Cell<usize>) {
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        v.replace_next_match(bytes).map(|offset| (offset, v.search_matches().len()))
+    }).unwrap();
+    let status = match result {
+        Ok((Some(offset), left)) => {
+            replaced.set(replaced.get() + 1);
+            format!("Replaced the match at 0x{:X}. {} matches left.", offset, left)
+        },
+        Ok((None, _)) => "No matches left.".to_string(),
+        Err(error) => {
+            s.show_error(error);
+            return;
+        },
+    };
+    s.call_on_name(OBJ_REPLACE_STATUS, |v: &mut TextView| v.set_content(status));
+}
+
+fn replace_all(s: &mut Cursive, bytes: &[u8]) {
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.replace_all_matches(bytes)).unwrap();
+    s.pop_layer();
+    match result {
+        Ok((replaced, 0)) => confirm_save(s, format!("Replaced {} matches.", replaced)),
+        Ok((replaced, skipped)) => confirm_save(s, format!(
+            "Replaced {} matches, and skipped {} that were overlapping or of a different length.", replaced, skipped)),
+        Err(error) => s.show_error(error),
+    }
+}
+
+fn finish(s: &mut Cursive, replaced: usize) {
+    s.pop_layer();
+    if replaced > 0 {
+        confirm_save(s, format!("Replaced {} matches.", replaced));
+    }
+}
+
+/// The replacements are only made in memory, until the user decides to save them, or to save a copy.
+fn confirm_save(s: &mut Cursive, message: String) {
+    let dialog = Dialog::text(format!("{}\n\nSave the changes to the file now?", message))
+        .title("Save replacements")
+        .button("Save", |s| {
+            s.pop_layer();
+            let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.save()).unwrap();
+            if let Err(error) = result {
+                s.show_error(error);
+            }
+        })
+        .button("Save copy", |s| {
+            s.pop_layer();
+            open_save_copy_dialog(s);
+        })
+        .dismiss_button("Later");
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+    s.add_layer(esc_view);
+}
+
+pub fn open_save_copy_dialog(s: &mut Cursive) {
+    let copy_name = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        if let Some(compression) = v.compression() {
+            // Save the decompressed copy next to the compressed file.
+            let path = v.file_path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            return path.with_file_name(compression.decompressed_name(&name)).into_os_string();
+        }
+        if let Some(member_name) = v.member_name() {
+            // Save the extracted member next to the archive.
+            let name = Path::new(&member_name).file_name().unwrap_or_default().to_os_string();
+            return v.file_path().with_file_name(name).into_os_string();
+        }
+        if !v.can_save() {
+            // Streams are kept in a temporary file, and process memory is not a file,
+            // so save the copy in the current directory instead.
+            return OsString::from(v.file_name());
+        }
+        let mut copy_name = v.file_path().into_os_string();
+        copy_name.push(".patched");
+        copy_name
+    }).unwrap();
+
+    let path_field = EditView::new()
+        .content(copy_name.to_string_lossy())
+        .on_submit(|s, _| do_save_copy(s))
+        .with_name(OBJ_SAVE_COPY_PATH)
+        .min_width(50);
+
+    let dialog = Dialog::around(LinearLayout::vertical()
+            .child(TextView::new("Save the edited file as:"))
+            .child(path_field))
+        .title("Save copy")
+        .dismiss_button("Cancel")
+        .button("Save", do_save_copy);
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+    s.add_layer(esc_view);
+}
+
+fn do_save_copy(s: &mut Cursive) {
+    let path = s.call_on_name(OBJ_SAVE_COPY_PATH, get_content).unwrap();
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        let path = Path::new(path.as_str());
+        v.save_copy(path)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        v.show_message(format!("Saved copy as {}", name));
+        Ok(())
+    }).unwrap();
+    match result {
+        Ok(()) => {
+            s.pop_layer();
+        },
+        Err(error) => s.show_error(error),
+    }
+}
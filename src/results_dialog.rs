@@ -0,0 +1,165 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use cursive::event::Key;
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::views::{Dialog, DummyView, EditView, LinearLayout, OnEventView, SelectView, TextView};
+use cursive::Cursive;
+
+use crate::hex_tables::BYTE_RENDER;
+use crate::hex_view::HexView;
+use crate::utilities::get_content;
+use crate::xxv_tui::{ShowError, OBJ_HEX_VIEW, OBJ_RESULTS_FILTER, OBJ_RESULTS_LIST, OBJ_RESULTS_ORDER};
+
+/// Reading a preview for every match takes a while, so very long lists are cut short.
+const MAX_RESULT_ROWS: usize = 10_000;
+/// The number of bytes before and after the start of each match, that are shown in its preview.
+const PREVIEW_BEFORE: u64 = 4;
+const PREVIEW_AFTER: u64 = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ResultOrder {
+    Offset,
+    OffsetDescending,
+    Length,
+}
+
+impl ResultOrder {
+    const ALL: [ResultOrder; 3] = [ResultOrder::Offset, ResultOrder::OffsetDescending, ResultOrder::Length];
+
+    fn name(&self) -> &'static str {
+        match self {
+            ResultOrder::Offset => "Offset",
+            ResultOrder::OffsetDescending => "Offset, descending",
+            ResultOrder::Length => "Longest first",
+        }
+    }
+}
+
+struct ResultRow {
+    offset: u64,
+    length: u64,
+    label: String,
+}
+
+pub fn open_results_dialog(s: &mut Cursive) {
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        let matches = v.search_matches();
+        if matches.is_empty() {
+            v.show_message("No search results. Press / to search.".to_string());
+            return Ok(None);
+        }
+        let rows = result_rows(v, &matches[..matches.len().min(MAX_RESULT_ROWS)])?;
+        Ok(Some((rows, matches.len())))
+    }).unwrap();
+    let (rows, total) = match result {
+        Ok(Some(rows)) => rows,
+        Ok(None) => return,
+        Err(error) => {
+            s.show_error(error);
+            return;
+        }
+    };
+    let rows = Rc::new(rows);
+    let count_text = if total > rows.len() {
+        format!("Showing the first {} of {} matches.", rows.len(), total)
+    } else {
+        format!("{} matches.", total)
+    };
+
+    let filter_rows = rows.clone();
+    let filter_field = EditView::new()
+        .on_edit(move |s, _, _| fill_list(s, &filter_rows))
+        .with_name(OBJ_RESULTS_FILTER)
+        .min_width(30);
+
+    let order_rows = rows.clone();
+    let mut orders = SelectView::new().popup();
+    for order in ResultOrder::ALL.iter() {
+        orders.add_item(order.name(), *order);
+    }
+    orders.set_on_submit(move |s, _| fill_list(s, &order_rows));
+
+    let list: SelectView<u64> = SelectView::new().on_submit(go_to_result);
+
+    let layout = LinearLayout::vertical()
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("Filter: "))
+            .child(filter_field)
+            .child(TextView::new("  Sort: "))
+            .child(orders.with_name(OBJ_RESULTS_ORDER)))
+        .child(DummyView)
+        .child(list.with_name(OBJ_RESULTS_LIST).scrollable().max_height(20))
+        .child(DummyView)
+        .child(TextView::new(count_text));
+
+    let dialog = Dialog::around(layout)
+        .dismiss_button("Close")
+        .title("Search results");
+
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(esc_view);
+    fill_list(s, &rows);
+}
+
+/// Read the bytes around each match, and lay them out in columns that are as wide as the largest offset needs.
+fn result_rows(view: &mut HexView, matches: &[(u64, u64)]) -> std::io::Result<Vec<ResultRow>> {
+    let last_offset = matches.iter().map(|&(offset, _)| offset).max().unwrap_or(0);
+    let longest = matches.iter().map(|&(_, length)| length).max().unwrap_or(0);
+    let hex_width = format!("0x{:X}", last_offset).len();
+    let decimal_width = format!("{}", last_offset).len();
+    let length_width = format!("+{}", longest).len();
+    let table = view.visual_table();
+    let hex = |bytes: &[u8]| {
+        let rendered: Vec<&str> = bytes.iter().map(|&b| BYTE_RENDER[usize::from(b)]).collect();
+        rendered.join(" ")
+    };
+    let visual = |bytes: &[u8]| bytes.iter().map(|&b| table[usize::from(b)]).collect::<String>();
+
+    let mut rows = Vec::with_capacity(matches.len());
+    for &(offset, length) in matches {
+        let preview_start = offset.saturating_sub(PREVIEW_BEFORE);
+        let bytes = view.read_range(preview_start, offset - preview_start + PREVIEW_AFTER)?;
+        let (before, after) = bytes.split_at(bytes.len().min(usize::try_from(offset - preview_start).unwrap()));
+        let label = format!(
+            "{:>hw$} {:>dw$} {:>lw$}  {:>11}  {:<23}  {:>4}{}",
+            format!("0x{:X}", offset), offset, format!("+{}", length),
+            hex(before), hex(after), visual(before), visual(after),
+            hw = hex_width, dw = decimal_width, lw = length_width);
+        rows.push(ResultRow { offset, length, label });
+    }
+    Ok(rows)
+}
+
+fn fill_list(s: &mut Cursive, rows: &[ResultRow]) {
+    let filter = s.call_on_name(OBJ_RESULTS_FILTER, get_content).unwrap().to_lowercase();
+    let order = s
+        .call_on_name(OBJ_RESULTS_ORDER, |v: &mut SelectView<ResultOrder>| v.selection())
+        .unwrap()
+        .map_or(ResultOrder::Offset, |o| *o);
+
+    let mut shown: Vec<&ResultRow> = rows.iter()
+        .filter(|row| filter.is_empty() || row.label.to_lowercase().contains(&filter))
+        .collect();
+    match order {
+        ResultOrder::Offset => shown.sort_by_key(|row| row.offset),
+        ResultOrder::OffsetDescending => shown.sort_by_key(|row| std::cmp::Reverse(row.offset)),
+        ResultOrder::Length => shown.sort_by_key(|row| (std::cmp::Reverse(row.length), row.offset)),
+    }
+
+    s.call_on_name(OBJ_RESULTS_LIST, |v: &mut SelectView<u64>| {
+        v.clear();
+        for row in shown {
+            v.add_item(row.label.clone(), row.offset);
+        }
+    });
+}
+
+fn go_to_result(s: &mut Cursive, offset: &u64) {
+    s.pop_layer();
+    s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.go_to_offset(*offset));
+}
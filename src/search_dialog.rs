@@ -1,12 +1,56 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+use std::rc::Rc;
+
 use cursive::Cursive;
-use cursive::views::{LinearLayout, TextView, EditView, OnEventView, Dialog};
+use cursive::views::{LinearLayout, TextView, EditView, OnEventView, Dialog, SelectView, Checkbox, DummyView};
 use cursive::traits::{Nameable, Resizable};
-use crate::xxv_tui::{OBJ_FIND_ASCII, OBJ_FIND_HEX, OBJ_HEX_VIEW};
+use cursive::event::EventResult;
+use cursive::View;
+use crate::xxv_tui::{ShowError, OBJ_FIND_ASCII, OBJ_FIND_HEX, OBJ_FIND_REGEX, OBJ_FIND_NUMBER, OBJ_FIND_NUMBER_TYPE,
+                     OBJ_FIND_BYTE_ORDER, OBJ_FIND_TOLERANCE, OBJ_FIND_IGNORE_CASE, OBJ_FIND_ENCODING,
+                     OBJ_FIND_RANGE_START, OBJ_FIND_RANGE_END, OBJ_FIND_REPLACE, OBJ_HEX_VIEW};
 use crate::hex_tables::{BYTE_RENDER, UNICODE_TEXT_TABLE};
 use cursive::event::Key;
 use crate::hex_view::HexView;
+use crate::search_matcher::{text_matcher, LiteralMatcher, MaskedMatcher, Matcher, RegexMatcher, TextEncoding};
+use crate::number_search::{number_matcher, ByteOrder, NumberType};
+use crate::utilities::{get_content, parse_number};
+use crate::xxv_state::{QueryMode, SearchQuery, XxvState};
+
+/// The fields that each describe what to search for. Only one kind of search can be filled in at a time.
+const QUERY_FIELDS: [&str; 4] = [OBJ_FIND_ASCII, OBJ_FIND_HEX, OBJ_FIND_REGEX, OBJ_FIND_NUMBER];
+
+/// The recent queries, and which of them is shown in the dialog, while cycling through them with Up and Down.
+struct HistoryCursor {
+    queries: Vec<SearchQuery>,
+    index: Option<usize>,
+}
+
+impl HistoryCursor {
+    /// The next older query, or None if there is no history.
+    fn older(&mut self) -> Option<SearchQuery> {
+        let last = self.queries.len().checked_sub(1)?;
+        let index = self.index.map_or(0, |i| (i + 1).min(last));
+        self.index = Some(index);
+        Some(self.queries[index].clone())
+    }
+    
+    /// The next newer query, or an empty query after the most recent one.
+    /// None if no query from the history is shown.
+    fn newer(&mut self) -> Option<Option<SearchQuery>> {
+        let index = self.index?;
+        self.index = index.checked_sub(1);
+        Some(self.index.map(|i| self.queries[i].clone()))
+    }
+}
 
 pub fn search_dialog(s: &mut Cursive) {
+    let queries = s.with_user_data(|state: &mut XxvState| state.search_history().to_vec()).unwrap_or_default();
+    let history = Rc::new(RefCell::new(HistoryCursor { queries, index: None }));
+    
     let ascii_field = EditView::new()
         .content("")
         .on_edit(edit_ascii)
@@ -14,6 +58,12 @@ pub fn search_dialog(s: &mut Cursive) {
         .with_name(OBJ_FIND_ASCII)
         .min_width(48);
     
+    let mut encodings = SelectView::new().popup();
+    for encoding in TextEncoding::ALL.iter() {
+        encodings.add_item(encoding.name(), *encoding);
+    }
+    encodings.set_on_submit(change_encoding);
+    
     let hex_field = EditView::new()
         .content("")
         .on_edit(edit_hex)
@@ -21,38 +71,203 @@ pub fn search_dialog(s: &mut Cursive) {
         .with_name(OBJ_FIND_HEX)
         .min_width(48);
     
+    let regex_field = EditView::new()
+        .content("")
+        .on_edit(edit_regex)
+        .on_submit(on_find)
+        .with_name(OBJ_FIND_REGEX)
+        .min_width(48);
+    
+    let number_field = EditView::new()
+        .content("")
+        .on_edit(edit_number)
+        .on_submit(on_find)
+        .with_name(OBJ_FIND_NUMBER)
+        .min_width(48);
+    
+    let mut number_types = SelectView::new().popup();
+    for number_type in NumberType::ALL.iter() {
+        number_types.add_item(number_type.name(), *number_type);
+    }
+    number_types.set_selection(1);
+    let mut byte_orders = SelectView::new().popup();
+    for order in ByteOrder::ALL.iter() {
+        byte_orders.add_item(order.name(), *order);
+    }
+    let tolerance_field = EditView::new()
+        .content("")
+        .on_submit(on_find)
+        .with_name(OBJ_FIND_TOLERANCE)
+        .min_width(10);
+    
     let layout = LinearLayout::vertical()
         .child(LinearLayout::horizontal()
-            .child(TextView::new("ASCII: "))
-            .child(ascii_field))
+            .child(TextView::new("ASCII:  "))
+            .child(with_history(ascii_field, &history)))
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("        "))
+            .child(Checkbox::new().on_change(|s, _| preview_search(s)).with_name(OBJ_FIND_IGNORE_CASE))
+            .child(TextView::new(" Ignore case  "))
+            .child(encodings.with_name(OBJ_FIND_ENCODING)))
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("HEX:    "))
+            .child(with_history(hex_field, &history)))
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("Regex:  "))
+            .child(with_history(regex_field, &history)))
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("Number: "))
+            .child(with_history(number_field, &history)))
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("        "))
+            .child(number_types.with_name(OBJ_FIND_NUMBER_TYPE))
+            .child(TextView::new(" "))
+            .child(byte_orders.with_name(OBJ_FIND_BYTE_ORDER))
+            .child(TextView::new(" Float tolerance: "))
+            .child(tolerance_field))
+        .child(DummyView)
         .child(LinearLayout::horizontal()
-            .child(TextView::new("HEX:   "))
-            .child(hex_field));
+            .child(TextView::new("Replace:"))
+            .child(EditView::new().with_name(OBJ_FIND_REPLACE).min_width(48)))
+        .child(LinearLayout::horizontal()
+            .child(TextView::new("From:   "))
+            .child(EditView::new().on_submit(on_find).with_name(OBJ_FIND_RANGE_START).min_width(18))
+            .child(TextView::new("  To: "))
+            .child(EditView::new().on_submit(on_find).with_name(OBJ_FIND_RANGE_END).min_width(18)));
     
     let dialog = Dialog::around(layout)
-        .dismiss_button("Cancel")
+        .button("Cancel", cancel)
+        .button("Replace", do_replace)
         .button("Search", do_find)
         .title("Search");
     
     let esc_view = OnEventView::new(dialog)
-        .on_event(Key::Esc, |s| {
-            s.pop_layer();
-        })
+        .on_event(Key::Esc, cancel)
         .on_event(Key::Enter, do_find);
     
     s.add_layer(esc_view);
 }
 
+/// Let Up and Down cycle through the recent queries, while the query field is focused.
+fn with_history<V: View>(view: V, history: &Rc<RefCell<HistoryCursor>>) -> OnEventView<V> {
+    let (older, newer) = (history.clone(), history.clone());
+    OnEventView::new(view)
+        .on_pre_event_inner(Key::Up, move |_, _| {
+            let query = older.borrow_mut().older()?;
+            Some(EventResult::with_cb(move |s| show_query(s, Some(&query))))
+        })
+        .on_pre_event_inner(Key::Down, move |_, _| {
+            let query = newer.borrow_mut().newer()?;
+            Some(EventResult::with_cb(move |s| show_query(s, query.as_ref())))
+        })
+}
+
+/// Fill in the query in the field for its mode, and clear the other query fields.
+fn show_query(s: &mut Cursive, query: Option<&SearchQuery>) {
+    for name in QUERY_FIELDS.iter() {
+        let callback = s.call_on_name(name, |v: &mut EditView| {
+            v.enable();
+            v.set_content("")
+        }).unwrap();
+        callback(s);
+    }
+    show_options(s, &query.cloned().unwrap_or_default());
+    if let Some(query) = query {
+        let name = query_field(query.mode);
+        let text = query.query.clone();
+        let callback = s.call_on_name(name, |v: &mut EditView| v.set_content(text)).unwrap();
+        callback(s);
+        s.focus_name(name).unwrap();
+    }
+}
+
+/// Select the options of the query, or the default options for the options it does not have.
+fn show_options(s: &mut Cursive, query: &SearchQuery) {
+    let number_type = NumberType::ALL.iter().position(|t| t.name() == query.number_type).unwrap_or(1);
+    let byte_order = ByteOrder::ALL.iter().position(|o| o.name() == query.byte_order).unwrap_or(0);
+    let encoding = TextEncoding::ALL.iter().position(|e| e.name() == query.encoding).unwrap_or(0);
+    s.call_on_name(OBJ_FIND_NUMBER_TYPE, |v: &mut SelectView<NumberType>| v.set_selection(number_type));
+    s.call_on_name(OBJ_FIND_BYTE_ORDER, |v: &mut SelectView<ByteOrder>| v.set_selection(byte_order));
+    s.call_on_name(OBJ_FIND_ENCODING, |v: &mut SelectView<TextEncoding>| v.set_selection(encoding));
+    s.call_on_name(OBJ_FIND_TOLERANCE, |v: &mut EditView| v.set_content(query.tolerance.clone()));
+    s.call_on_name(OBJ_FIND_IGNORE_CASE, |v: &mut Checkbox| v.set_checked(query.ignore_case));
+}
+
+fn query_field(mode: QueryMode) -> &'static str {
+    match mode {
+        QueryMode::Text => OBJ_FIND_ASCII,
+        QueryMode::Hex => OBJ_FIND_HEX,
+        QueryMode::Regex => OBJ_FIND_REGEX,
+        QueryMode::Number => OBJ_FIND_NUMBER,
+    }
+}
+
+/// The query that is filled in, in the same order of precedence as `build_matcher`.
+fn current_query(s: &mut Cursive) -> SearchQuery {
+    let has_text = |s: &mut Cursive, name: &str| !s.call_on_name(name, get_content).unwrap().is_empty();
+    let mode = if has_text(s, OBJ_FIND_REGEX) {
+        QueryMode::Regex
+    } else if has_text(s, OBJ_FIND_NUMBER) {
+        QueryMode::Number
+    } else if is_text_query(s) {
+        QueryMode::Text
+    } else {
+        QueryMode::Hex
+    };
+    let query = s.call_on_name(query_field(mode), get_content).unwrap().to_string();
+    let number_type = s.call_on_name(OBJ_FIND_NUMBER_TYPE, |v: &mut SelectView<NumberType>| v.selection()).unwrap();
+    let byte_order = s.call_on_name(OBJ_FIND_BYTE_ORDER, |v: &mut SelectView<ByteOrder>| v.selection()).unwrap();
+    SearchQuery {
+        mode,
+        query,
+        number_type: number_type.map_or_else(String::new, |t| t.name().to_string()),
+        byte_order: byte_order.map_or_else(String::new, |o| o.name().to_string()),
+        tolerance: s.call_on_name(OBJ_FIND_TOLERANCE, get_content).unwrap().to_string(),
+        encoding: selected_encoding(s).name().to_string(),
+        ignore_case: s.call_on_name(OBJ_FIND_IGNORE_CASE, |v: &mut Checkbox| v.is_checked()).unwrap(),
+    }
+}
+
+fn remember_query(s: &mut Cursive) {
+    let query = current_query(s);
+    if !query.query.is_empty() {
+        s.with_user_data(|state: &mut XxvState| state.add_search_query(query));
+    }
+}
+
 fn edit_ascii(s: &mut Cursive, text: &str, _cursor: usize) {
+    let encoding = selected_encoding(s);
     s.call_on_name(OBJ_FIND_HEX, |v: &mut EditView| {
-        let bytes = text.as_bytes();
+        let bytes = encoding.encode(text);
         let mut hex_text = String::new();
         for b in bytes {
-            hex_text.push_str(BYTE_RENDER[usize::from(*b)]);
+            hex_text.push_str(BYTE_RENDER[usize::from(b)]);
         }
         v.set_content(hex_text);
         update_companion_accessibility(text, v)
     });
+    update_other_queries(s, text, &[OBJ_FIND_ASCII, OBJ_FIND_HEX]);
+    preview_search(s);
+}
+
+fn change_encoding(s: &mut Cursive, _encoding: &TextEncoding) {
+    if is_text_query(s) {
+        let text = s.call_on_name(OBJ_FIND_ASCII, get_content).unwrap();
+        edit_ascii(s, &text, 0);
+    }
+}
+
+fn selected_encoding(s: &mut Cursive) -> TextEncoding {
+    s.call_on_name(OBJ_FIND_ENCODING, |v: &mut SelectView<TextEncoding>| v.selection())
+        .unwrap()
+        .map_or(TextEncoding::Utf8, |e| *e)
+}
+
+/// True if the text was typed into the ASCII field, rather than being shown for the bytes typed into the HEX field.
+fn is_text_query(s: &mut Cursive) -> bool {
+    let text = s.call_on_name(OBJ_FIND_ASCII, get_content).unwrap();
+    let hex_enabled = s.call_on_name(OBJ_FIND_HEX, |v: &mut EditView| v.is_enabled()).unwrap();
+    !text.is_empty() && !hex_enabled
 }
 
 fn edit_hex(s: &mut Cursive, text: &str, cursor: usize) {
@@ -60,53 +275,107 @@ fn edit_hex(s: &mut Cursive, text: &str, cursor: usize) {
     s.call_on_name(OBJ_FIND_HEX, |v: &mut EditView| {
         let mut hex_text = v.get_content().to_string();
         let mut bytes: Vec<u8> = Vec::new();
-        let removed_illegal_digits = hex_to_bytes(&mut hex_text, &mut bytes);
-        for b in bytes {
-            ascii.push_str(UNICODE_TEXT_TABLE[usize::from(b)]);
+        let mut mask: Vec<u8> = Vec::new();
+        let removed_illegal_digits = hex_to_pattern(&mut hex_text, &mut bytes, &mut mask);
+        for (b, m) in bytes.into_iter().zip(mask) {
+            ascii.push_str(if m == 0xFF { UNICODE_TEXT_TABLE[usize::from(b)] } else { "?" });
         }
         if removed_illegal_digits {
+            let removed = text.len() - hex_text.len();
             v.set_content(hex_text);
-            v.set_cursor(cursor - 1);
+            v.set_cursor(cursor.saturating_sub(removed));
         };
     });
     s.call_on_name(OBJ_FIND_ASCII, |v: &mut EditView| {
         v.set_content(ascii);
         update_companion_accessibility(text, v)
     });
+    update_other_queries(s, text, &[OBJ_FIND_ASCII, OBJ_FIND_HEX]);
+    preview_search(s);
 }
 
-fn hex_to_bytes(hex_text: &mut String, bytes: &mut Vec<u8>) -> bool {
-    let mut modified = false;
-    let mut i = 0;
-    while i < hex_text.len() {
-        let byte = if hex_text.len() > i + 1 {
-            &hex_text[i..=i+1]
-        } else {
-            &hex_text[i..=i]
-        };
-        if let Ok(mut byte_value) = u8::from_str_radix(byte, 16) {
-            if byte.len() == 1 {
-                byte_value = byte_value << 4;
-            }
-            bytes.push(byte_value);
-            i += 2;
-        } else {
-            // Remove characters that cannot be parsed as hexadecimal.
-            if let Ok(_) = u8::from_str_radix(&byte[0..=0], 16) {
-                hex_text.remove(i + 1);
-            } else {
-                hex_text.remove(i);
-            }
-            modified = true;
+/// Highlight the matches of the text or hex query in the viewport while it is typed,
+/// before the whole file is searched.
+fn preview_search(s: &mut Cursive) {
+    let text = s.call_on_name(OBJ_FIND_ASCII, get_content).unwrap();
+    if text.is_empty() {
+        s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.end_preview());
+        return;
+    }
+    let matcher = match build_matcher(s) {
+        Ok(matcher) => matcher,
+        Err(_) => {
+            // The query is not complete yet, like a hex pattern of only wildcards, so there is nothing to preview.
+            s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.end_preview());
+            return;
         }
+    };
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.preview_search(matcher.as_ref())).unwrap();
+    if let Err(error) = result {
+        s.show_error(error);
+    }
+}
+
+fn cancel(s: &mut Cursive) {
+    s.pop_layer();
+    s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.end_preview());
+}
+
+fn edit_regex(s: &mut Cursive, text: &str, _cursor: usize) {
+    update_other_queries(s, text, &[OBJ_FIND_REGEX]);
+}
+
+fn edit_number(s: &mut Cursive, text: &str, _cursor: usize) {
+    update_other_queries(s, text, &[OBJ_FIND_NUMBER]);
+}
+
+/// Disable the query fields that are not part of the edited query, while it has text in it.
+fn update_other_queries(s: &mut Cursive, text: &str, edited: &[&str]) {
+    for name in QUERY_FIELDS.iter().filter(|name| !edited.contains(name)) {
+        s.call_on_name(name, |v: &mut EditView| update_companion_accessibility(text, v));
+    }
+}
+
+/// Parse hex digits into bytes, and a mask of the bits that must match.
+/// A `?` is a wildcard for one hex digit, so `??` matches any byte, and `8?` matches 80 to 8F.
+/// Spaces are ignored, and other characters are removed from the text, in which case true is returned.
+pub fn hex_to_pattern(hex_text: &mut String, bytes: &mut Vec<u8>, mask: &mut Vec<u8>) -> bool {
+    let length = hex_text.len();
+    hex_text.retain(|c| c.is_ascii_hexdigit() || c == '?' || c == ' ');
+    let digits: Vec<(u8, u8)> = hex_text.chars()
+        .filter(|&c| c != ' ')
+        .map(|c| match c.to_digit(16) {
+            Some(digit) => (u8::try_from(digit).unwrap(), 0xF),
+            None => (0, 0),
+        })
+        .collect();
+    for pair in digits.chunks(2) {
+        let (high, high_mask) = pair[0];
+        // A lone digit at the end is the high half of the byte.
+        let (low, low_mask) = pair.get(1).copied().unwrap_or((0, 0xF));
+        bytes.push(high << 4 | low);
+        mask.push(high_mask << 4 | low_mask);
     }
-    return modified;
+    hex_text.len() != length
+}
+
+/// Parse hex digits into bytes, where spaces between the bytes are ignored.
+/// Returns None if there are any other characters, or an odd number of digits.
+pub fn hex_to_bytes(hex_text: &str) -> Option<Vec<u8>> {
+    let digits = hex_text.chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|c| c.to_digit(16).map(|digit| u8::try_from(digit).unwrap()))
+        .collect::<Option<Vec<u8>>>()?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(digits.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect())
 }
 
 fn update_companion_accessibility(text: &str, v: &mut EditView) {
-    if text.len() > 0 && v.is_enabled() {
+    if !text.is_empty() && v.is_enabled() {
         v.disable();
-    } else if text.len() == 0 && !v.is_enabled() {
+    } else if text.is_empty() && !v.is_enabled() {
         v.enable();
     }
 }
@@ -116,13 +385,175 @@ fn on_find(s: &mut Cursive, _text: &str) {
 }
 
 fn do_find(s: &mut Cursive) {
-    let mut bytes: Vec<u8> = Vec::new();
-    s.call_on_name(OBJ_FIND_HEX, |v: &mut EditView| {
-        let mut contents = v.get_content().to_string();
-        hex_to_bytes(&mut contents, &mut bytes);
-    });
+    let query = build_matcher(s).and_then(|matcher| Ok((matcher, search_range(s)?)));
+    let (matcher, range) = match query {
+        Ok(query) => query,
+        Err(error) => {
+            // Keep the dialog open, so the query can be fixed.
+            s.show_error(error);
+            return;
+        }
+    };
+    remember_query(s);
     s.pop_layer();
-    s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
-        v.search(&bytes);
+    let sink = s.cb_sink().clone();
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| v.search(matcher, range, sink)).unwrap();
+    if let Err(error) = result {
+        s.show_error(error);
+    }
+}
+
+fn do_replace(s: &mut Cursive) {
+    let query = build_matcher(s).and_then(|matcher| {
+        let range = search_range(s)?;
+        let replacement = build_replacement(s)?;
+        if matcher.is_fixed_length() && matcher.max_match_length() != replacement.len() {
+            let message = format!("The replacement must be as long as the matches, which are {} bytes long.",
+                matcher.max_match_length());
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        Ok((matcher, range, replacement))
     });
+    let (matcher, range, replacement) = match query {
+        Ok(query) => query,
+        Err(error) => {
+            s.show_error(error);
+            return;
+        }
+    };
+    remember_query(s);
+    s.pop_layer();
+    let sink = s.cb_sink().clone();
+    let result = s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
+        v.search(matcher, range, sink)?;
+        // The replace dialog is opened when the search is done.
+        v.set_pending_replacement(replacement);
+        Ok(())
+    }).unwrap();
+    if let Err(error) = result {
+        s.show_error(error);
+    }
+}
+
+/// The replacement is text in the chosen encoding when searching for text, and hex bytes otherwise.
+fn build_replacement(s: &mut Cursive) -> Result<Vec<u8>> {
+    let text = s.call_on_name(OBJ_FIND_REPLACE, get_content).unwrap();
+    let bytes = if is_text_query(s) {
+        selected_encoding(s).encode(&text)
+    } else {
+        if text.contains('?') {
+            return Err(Error::new(ErrorKind::InvalidInput, "The replacement cannot have wildcards."));
+        }
+        hex_to_bytes(&text)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "The replacement must be whole hex bytes, like 4D 5A."))?
+    };
+    if bytes.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "Fill in the replacement."));
+    }
+    Ok(bytes)
+}
+
+/// The range of offsets to search, where an empty start means the start of the file,
+/// and an empty end means the end of the file. The end offset is not included in the range.
+fn search_range(s: &mut Cursive) -> Result<Range<u64>> {
+    let start = s.call_on_name(OBJ_FIND_RANGE_START, get_content).unwrap();
+    let end = s.call_on_name(OBJ_FIND_RANGE_END, get_content).unwrap();
+    let parse = |text: &str, default: u64| {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(default);
+        }
+        parse_number(text).map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Not an offset: {} ({})", text, e)))
+    };
+    let range = parse(&start, 0)?..parse(&end, u64::MAX)?;
+    if range.start > range.end {
+        return Err(Error::new(ErrorKind::InvalidInput, "The search range ends before it starts."));
+    }
+    Ok(range)
+}
+
+fn build_matcher(s: &mut Cursive) -> Result<Box<dyn Matcher>> {
+    let pattern = s.call_on_name(OBJ_FIND_REGEX, get_content).unwrap();
+    if !pattern.is_empty() {
+        return Ok(Box::new(RegexMatcher::new(&pattern)?));
+    }
+    
+    let number = s.call_on_name(OBJ_FIND_NUMBER, get_content).unwrap();
+    if !number.is_empty() {
+        let number_type = s
+            .call_on_name(OBJ_FIND_NUMBER_TYPE, |v: &mut SelectView<NumberType>| v.selection())
+            .unwrap()
+            .map_or(NumberType::U32, |t| *t);
+        let order = s
+            .call_on_name(OBJ_FIND_BYTE_ORDER, |v: &mut SelectView<ByteOrder>| v.selection())
+            .unwrap()
+            .map_or(ByteOrder::Little, |o| *o);
+        let tolerance = s.call_on_name(OBJ_FIND_TOLERANCE, get_content).unwrap();
+        return number_matcher(&number, number_type, order, &tolerance);
+    }
+    
+    if is_text_query(s) {
+        let text = s.call_on_name(OBJ_FIND_ASCII, get_content).unwrap();
+        let ignore_case = s.call_on_name(OBJ_FIND_IGNORE_CASE, |v: &mut Checkbox| v.is_checked()).unwrap();
+        return Ok(text_matcher(&text, selected_encoding(s), ignore_case));
+    }
+    
+    let hex_text = s.call_on_name(OBJ_FIND_HEX, get_content).unwrap();
+    hex_matcher(&hex_text)
+}
+
+fn hex_matcher(hex_text: &str) -> Result<Box<dyn Matcher>> {
+    let mut contents = hex_text.to_string();
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut mask: Vec<u8> = Vec::new();
+    hex_to_pattern(&mut contents, &mut bytes, &mut mask);
+    if !mask.is_empty() && mask.iter().all(|&m| m == 0) {
+        // Such a pattern would match at every offset.
+        return Err(Error::new(ErrorKind::InvalidInput, "The hex pattern must have at least one digit that is not a wildcard."));
+    }
+    if mask.iter().all(|&m| m == 0xFF) {
+        Ok(Box::new(LiteralMatcher::new(&bytes)))
+    } else {
+        Ok(Box::new(MaskedMatcher::new(&bytes, &mask)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (String, Vec<u8>, Vec<u8>) {
+        let mut text = text.to_string();
+        let mut bytes = Vec::new();
+        let mut mask = Vec::new();
+        hex_to_pattern(&mut text, &mut bytes, &mut mask);
+        (text, bytes, mask)
+    }
+
+    #[test]
+    fn parsing_hex_patterns() {
+        assert_eq!(parse("4d5A"), ("4d5A".to_string(), vec![0x4D, 0x5A], vec![0xFF, 0xFF]));
+        assert_eq!(parse("4D 5A ?? ?? 50 45"),
+            ("4D 5A ?? ?? 50 45".to_string(), vec![0x4D, 0x5A, 0, 0, 0x50, 0x45], vec![0xFF, 0xFF, 0, 0, 0xFF, 0xFF]));
+        assert_eq!(parse("8??1"), ("8??1".to_string(), vec![0x80, 0x01], vec![0xF0, 0x0F]));
+        assert_eq!(parse("4x1g2"), ("412".to_string(), vec![0x41, 0x20], vec![0xFF, 0xFF]));
+    }
+
+    #[test]
+    fn parsing_whole_hex_bytes() {
+        assert_eq!(hex_to_bytes("4d5A"), Some(vec![0x4D, 0x5A]));
+        assert_eq!(hex_to_bytes(" 41 42\t43 "), Some(vec![0x41, 0x42, 0x43]));
+        assert_eq!(hex_to_bytes(""), Some(vec![]));
+        assert_eq!(hex_to_bytes("0xff"), None);
+        assert_eq!(hex_to_bytes("41 4"), None);
+        assert_eq!(hex_to_bytes("4?"), None);
+    }
+
+    #[test]
+    fn hex_patterns_need_a_digit_that_is_not_a_wildcard() {
+        assert!(hex_matcher("??").is_err());
+        assert!(hex_matcher("?? ?? ??").is_err());
+        assert!(hex_matcher("?? 4?").is_ok());
+        assert_eq!(hex_matcher("?? 41").unwrap().find_at(b"AAA", 0), Some((0, 2)));
+    }
 }
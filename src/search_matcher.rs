@@ -0,0 +1,328 @@
+use std::io::{Error, ErrorKind, Result};
+
+use bstr::Finder;
+use regex::bytes::{Regex, RegexBuilder};
+
+/// How far back a regex search reaches into the previous block, since we cannot tell how long a regex match can be.
+/// Matches that are longer than this may be missed, if they span two blocks.
+const REGEX_OVERLAP: usize = 64 * 1024;
+
+/// Finds matches in blocks of bytes.
+pub trait Matcher: Send {
+    /// Find the first match that starts at or after `start`, and return its start and length.
+    fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)>;
+
+    /// The longest match that is guaranteed to be found, even if it spans two blocks.
+    fn max_match_length(&self) -> usize;
+
+    /// True if all matches have the same length, which means a match at the end of a block is never cut short.
+    fn is_fixed_length(&self) -> bool {
+        false
+    }
+
+    /// Where to look for the next match, after a match at the given start, with the given length.
+    fn next_start(&self, start: usize, length: usize) -> usize {
+        start + length.max(1)
+    }
+}
+
+/// Matches an exact sequence of bytes. Overlapping matches are all found.
+pub struct LiteralMatcher {
+    finder: Finder<'static>,
+}
+
+impl LiteralMatcher {
+    pub fn new(bytes: &[u8]) -> LiteralMatcher {
+        LiteralMatcher { finder: Finder::new(bytes).into_owned() }
+    }
+}
+
+impl Matcher for LiteralMatcher {
+    fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+        let p = self.finder.find(haystack.get(start..)?)?;
+        Some((start + p, self.finder.needle().len()))
+    }
+
+    fn max_match_length(&self) -> usize {
+        self.finder.needle().len()
+    }
+
+    fn is_fixed_length(&self) -> bool {
+        true
+    }
+
+    fn next_start(&self, start: usize, _length: usize) -> usize {
+        start + 1
+    }
+}
+
+/// Matches any of several byte sequences, which all have the same length.
+pub struct AlternativesMatcher {
+    regex: Regex,
+    length: usize,
+}
+
+impl AlternativesMatcher {
+    pub fn new(alternatives: &[Vec<u8>]) -> AlternativesMatcher {
+        let length = alternatives[0].len();
+        assert!(alternatives.iter().all(|a| a.len() == length), "Alternatives must have the same length");
+        let escaped: Vec<String> = alternatives.iter()
+            .map(|a| a.iter().map(|b| format!("\\x{:02x}", b)).collect())
+            .collect();
+        let regex = RegexBuilder::new(&escaped.join("|")).unicode(false).build().unwrap();
+        AlternativesMatcher { regex, length }
+    }
+}
+
+impl Matcher for AlternativesMatcher {
+    fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+        if start > haystack.len() {
+            return None;
+        }
+        self.regex.find_at(haystack, start).map(|m| (m.start(), self.length))
+    }
+
+    fn max_match_length(&self) -> usize {
+        self.length
+    }
+
+    fn is_fixed_length(&self) -> bool {
+        true
+    }
+
+    fn next_start(&self, start: usize, _length: usize) -> usize {
+        start + 1
+    }
+}
+
+/// Matches a sequence of bytes where some bits can have any value, like `4D 5A ?? ?? 50 45` or `8?`.
+/// A byte matches when `haystack_byte & mask == byte & mask`.
+pub struct MaskedMatcher {
+    bytes: Vec<u8>,
+    mask: Vec<u8>,
+    /// The longest run of bytes without wildcards, and where it is in the pattern.
+    /// Candidates are found by searching for this run, and then checking the rest of the pattern.
+    anchor: Option<(usize, Finder<'static>)>,
+}
+
+impl MaskedMatcher {
+    pub fn new(bytes: &[u8], mask: &[u8]) -> MaskedMatcher {
+        let bytes: Vec<u8> = bytes.iter().zip(mask).map(|(b, m)| b & m).collect();
+        let mut anchor: Option<(usize, usize)> = None;
+        let mut run_start = 0;
+        for i in 0..=mask.len() {
+            if i == mask.len() || mask[i] != 0xFF {
+                let run_length = i - run_start;
+                if run_length > 0 && anchor.is_none_or(|(_, length)| run_length > length) {
+                    anchor = Some((run_start, run_length));
+                }
+                run_start = i + 1;
+            }
+        }
+        let anchor = anchor.map(|(start, length)| (start, Finder::new(&bytes[start..start + length]).into_owned()));
+        MaskedMatcher { bytes, mask: mask.to_vec(), anchor }
+    }
+
+    fn matches_at(&self, haystack: &[u8], start: usize) -> bool {
+        match haystack.get(start..start + self.bytes.len()) {
+            Some(candidate) => candidate.iter().zip(&self.mask).zip(&self.bytes).all(|((h, m), b)| h & m == *b),
+            None => false,
+        }
+    }
+}
+
+impl Matcher for MaskedMatcher {
+    fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+        let length = self.bytes.len();
+        match &self.anchor {
+            Some((anchor_offset, finder)) => {
+                let mut search_from = start + anchor_offset;
+                while let Some(p) = finder.find(haystack.get(search_from..)?) {
+                    let candidate = search_from + p - anchor_offset;
+                    if self.matches_at(haystack, candidate) {
+                        return Some((candidate, length));
+                    }
+                    search_from += p + 1;
+                }
+                None
+            },
+            // Every byte has a wildcard, so there is nothing better to do than to check every position.
+            None => (start..haystack.len()).find(|&p| self.matches_at(haystack, p)).map(|p| (p, length)),
+        }
+    }
+
+    fn max_match_length(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn is_fixed_length(&self) -> bool {
+        true
+    }
+
+    fn next_start(&self, start: usize, _length: usize) -> usize {
+        start + 1
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    pub const ALL: [TextEncoding; 3] = [TextEncoding::Utf8, TextEncoding::Utf16Le, TextEncoding::Utf16Be];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16 LE",
+            TextEncoding::Utf16Be => "UTF-16 BE",
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            TextEncoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            TextEncoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        }
+    }
+}
+
+/// Build a matcher for the text in the given encoding.
+/// Ignoring case only applies to ASCII letters, where the upper and lower case letters differ by one bit.
+pub fn text_matcher(text: &str, encoding: TextEncoding, ignore_case: bool) -> Box<dyn Matcher> {
+    let bytes = encoding.encode(text);
+    if !ignore_case {
+        return Box::new(LiteralMatcher::new(&bytes));
+    }
+    let mut mask = vec![0xFF; bytes.len()];
+    let unit_length = if encoding == TextEncoding::Utf8 { 1 } else { 2 };
+    let letter_index = if encoding == TextEncoding::Utf16Be { 1 } else { 0 };
+    for (unit, unit_mask) in bytes.chunks(unit_length).zip(mask.chunks_mut(unit_length)) {
+        let is_ascii = unit.iter().enumerate().all(|(i, &b)| i == letter_index || b == 0);
+        if is_ascii && unit[letter_index].is_ascii_alphabetic() {
+            unit_mask[letter_index] = !0x20;
+        }
+    }
+    Box::new(MaskedMatcher::new(&bytes, &mask))
+}
+
+/// Matches a regular expression on the raw bytes, where `\xNN` matches the byte NN, rather than a unicode character.
+pub struct RegexMatcher {
+    regex: Regex,
+}
+
+impl RegexMatcher {
+    pub fn new(pattern: &str) -> Result<RegexMatcher> {
+        let regex = RegexBuilder::new(pattern)
+            .unicode(false)
+            .build()
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+        Ok(RegexMatcher { regex })
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+        let mut start = start;
+        while start <= haystack.len() {
+            let m = self.regex.find_at(haystack, start)?;
+            if m.end() > m.start() {
+                return Some((m.start(), m.end() - m.start()));
+            }
+            // Empty matches are of no use in a hex viewer.
+            start = m.start() + 1;
+        }
+        None
+    }
+
+    fn max_match_length(&self) -> usize {
+        REGEX_OVERLAP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_all(matcher: &dyn Matcher, haystack: &[u8]) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while let Some((s, len)) = matcher.find_at(haystack, start) {
+            matches.push((s, len));
+            start = matcher.next_start(s, len);
+        }
+        matches
+    }
+
+    #[test]
+    fn literal_matches_overlap() {
+        let matcher = LiteralMatcher::new(b"aba");
+        assert_eq!(find_all(&matcher, b"ababa"), vec![(0, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn alternatives_can_overlap() {
+        let matcher = AlternativesMatcher::new(&[b"ab".to_vec(), b"ba".to_vec()]);
+        assert_eq!(find_all(&matcher, b"xabay"), vec![(1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn masked_matches() {
+        let matcher = MaskedMatcher::new(&[0x4D, 0x5A, 0x00, 0x00, 0x50, 0x45], &[0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF]);
+        assert_eq!(find_all(&matcher, b"MZ\x01\x02PE MZPE MZ\xFF\xFFPE MZ\x00"), vec![(0, 6), (12, 6)]);
+        let matcher = MaskedMatcher::new(&[0x80, 0x01], &[0xF0, 0xFF]);
+        assert_eq!(find_all(&matcher, b"\x7F\x01\x8A\x01\x8F\x02\x80\x01"), vec![(2, 2), (6, 2)]);
+    }
+
+    #[test]
+    fn masked_matches_without_fixed_bytes() {
+        let matcher = MaskedMatcher::new(&[0x00, 0x40], &[0x00, 0xF0]);
+        assert_eq!(find_all(&matcher, b"AB\x00C"), vec![(0, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn text_in_other_encodings() {
+        let matcher = text_matcher("Hi", TextEncoding::Utf16Le, false);
+        assert_eq!(find_all(matcher.as_ref(), b"H\0i\0 \0Hi\0"), vec![(0, 4)]);
+        let matcher = text_matcher("Hi", TextEncoding::Utf16Be, false);
+        assert_eq!(find_all(matcher.as_ref(), b"H\0i\0\0H\0i"), vec![(4, 4)]);
+        let matcher = text_matcher("€", TextEncoding::Utf16Le, false);
+        assert_eq!(find_all(matcher.as_ref(), b"\xAC\x20"), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn text_ignoring_case() {
+        let matcher = text_matcher("Key1", TextEncoding::Utf8, true);
+        assert_eq!(find_all(matcher.as_ref(), b"KEY1 key1 kEy! keyQ"), vec![(0, 4), (5, 4)]);
+        let matcher = text_matcher("ab", TextEncoding::Utf16Be, true);
+        assert_eq!(find_all(matcher.as_ref(), b"\0A\0b \0a\x20b"), vec![(0, 4)]);
+        // Only the letters are case folded, so '@' does not match '`'.
+        let matcher = text_matcher("@", TextEncoding::Utf8, true);
+        assert_eq!(find_all(matcher.as_ref(), b"`@"), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn regex_matches_bytes() {
+        let matcher = RegexMatcher::new(r"PK\x03\x04.{2}").unwrap();
+        assert_eq!(find_all(&matcher, b"\xFFPK\x03\x04\x80\x81PK"), vec![(1, 6)]);
+        let matcher = RegexMatcher::new(r"[\x20-\x7e]{4,}").unwrap();
+        assert_eq!(find_all(&matcher, b"ab\x00abcd\xFFabcdef"), vec![(3, 4), (8, 6)]);
+        let matcher = RegexMatcher::new(r"\xFF+").unwrap();
+        assert_eq!(find_all(&matcher, b"\xFE\xFF\xFF"), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn empty_regex_matches_are_skipped() {
+        let matcher = RegexMatcher::new(r"a*").unwrap();
+        assert_eq!(find_all(&matcher, b"xaax"), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn invalid_regex() {
+        assert_eq!(RegexMatcher::new(r"(").err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -73,7 +73,7 @@ fn do_set_widths(s: &mut Cursive) {
     if !group.is_empty() {
         match parse_number(&group) {
             Ok(group) => s.call_on_name(OBJ_HEX_VIEW, |v: &mut HexView| {
-                if group > 0 && group < u64::from(std::u16::MAX) {
+                if group > 0 && group < u64::from(u16::MAX) {
                     v.set_group(u16::try_from(group).unwrap());
                 }
             }),
@@ -0,0 +1,101 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Result, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tempfile::TempPath;
+
+const SPOOL_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Copies a stream, such as stdin or a pipe, into a temporary file in the background.
+/// The file can then be read at any offset, while more input is still arriving.
+/// The temporary file is deleted when the spool is dropped.
+#[derive(Debug)]
+pub struct Spool {
+    path: TempPath,
+    progress: SpoolProgress,
+}
+
+/// How much of the input has been copied so far, and whether all of it has.
+#[derive(Clone, Debug, Default)]
+pub struct SpoolProgress {
+    state: Arc<SpoolState>,
+}
+
+#[derive(Debug, Default)]
+struct SpoolState {
+    received: AtomicU64,
+    finished: AtomicBool,
+    stopped: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+impl Spool {
+    pub fn start<R: Read + Send + 'static>(mut input: R) -> Result<Spool> {
+        let (mut file, path) = tempfile::Builder::new().prefix("xv-input-").tempfile()?.into_parts();
+        let progress = SpoolProgress::default();
+        let thread_progress = progress.clone();
+        thread::Builder::new().name("xv-spool".to_string()).spawn(move || {
+            let state = &thread_progress.state;
+            if let Err(error) = copy_input(&mut input, &mut file, state) {
+                *state.error.lock().unwrap() = Some(error.to_string());
+            }
+            state.finished.store(true, Ordering::Release);
+        })?;
+        Ok(Spool { path, progress })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn progress(&self) -> &SpoolProgress {
+        &self.progress
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        // Nobody will read the rest of the input, so stop copying it.
+        self.progress.state.stopped.store(true, Ordering::Release);
+    }
+}
+
+impl SpoolProgress {
+    /// The number of bytes that can be read from the temporary file.
+    pub fn received(&self) -> u64 {
+        self.state.received.load(Ordering::Acquire)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::Acquire)
+    }
+
+    /// Whether both are the progress of copying the same input.
+    pub fn is_same_input(&self, other: &SpoolProgress) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+
+    /// The error that stopped the copying, if any.
+    pub fn error(&self) -> Option<String> {
+        self.state.error.lock().unwrap().clone()
+    }
+}
+
+fn copy_input(input: &mut dyn Read, file: &mut File, state: &SpoolState) -> Result<()> {
+    let mut buf = vec![0; SPOOL_BUFFER_SIZE];
+    while !state.stopped.load(Ordering::Acquire) {
+        let bytes_read = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        file.write_all(&buf[..bytes_read])?;
+        state.received.fetch_add(u64::try_from(bytes_read).unwrap(), Ordering::AcqRel);
+    }
+    Ok(())
+}
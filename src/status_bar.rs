@@ -1,9 +1,9 @@
 use cursive::theme::{ColorStyle, ColorType, Effect, PaletteColor, Style};
 use cursive::traits::Resizable;
 use cursive::utils::markup::StyledString;
-use cursive::views::{LinearLayout, PaddedView, TextView};
+use cursive::views::{Layer, LinearLayout, PaddedView, TextContent, TextView};
 
-pub fn new_status_bar() -> PaddedView<LinearLayout> {
+pub fn new_status_bar(status: TextContent) -> Layer<PaddedView<LinearLayout>> {
     let hints_style = ColorStyle::new(
         ColorType::Palette(PaletteColor::Tertiary),
         ColorType::Palette(PaletteColor::Background),
@@ -15,6 +15,8 @@ pub fn new_status_bar() -> PaddedView<LinearLayout> {
     let mut hints_bar_string = StyledString::new();
     hints_bar_string.append_styled("Q", hint_key_style);
     hints_bar_string.append_styled("uit   ", hints_style);
+    hints_bar_string.append_styled("E", hint_key_style);
+    hints_bar_string.append_styled("dit   ", hints_style);
     hints_bar_string.append_styled("G", hint_key_style);
     hints_bar_string.append_styled("o to   ", hints_style);
     hints_bar_string.append_styled("O", hint_key_style);
@@ -27,12 +29,16 @@ pub fn new_status_bar() -> PaddedView<LinearLayout> {
     hints_bar_string.append_styled("idth   ", hints_style);
 
     let hints_bar = TextView::new(hints_bar_string);
+    let status_view = TextView::new_with_content(status).style(hints_style).no_wrap();
 
-    PaddedView::lrtb(
+    // The layer clears the background, so no remains of a longer status are left behind.
+    Layer::with_color(PaddedView::lrtb(
         1,
         1,
         0,
         0,
-        LinearLayout::horizontal().child(hints_bar.full_width()),
-    )
+        LinearLayout::horizontal()
+            .child(hints_bar.full_width())
+            .child(status_view),
+    ), hints_style)
 }
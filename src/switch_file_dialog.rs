@@ -1,23 +1,25 @@
-use std::ffi::OsString;
-use std::path::PathBuf;
-
 use cursive::event::Key;
 use cursive::traits::{Resizable, Nameable};
 use cursive::views::{Dialog, LinearLayout, OnEventView, ScrollView, SelectView};
 use cursive::Cursive;
 
 use crate::hex_view::HexView;
-use crate::xxv_state::XxvState;
-use crate::xxv_tui::{ShowError, OBJ_SWITCHER, OBJ_HEX_VIEW};
+use crate::xxv_state::{ReaderState, XxvState};
+use crate::xxv_tui::{confirm_unsaved_changes, offer_decompression, watch_input, ShowError, OBJ_SWITCHER, OBJ_HEX_VIEW};
 
 pub fn switch_file_dialog(s: &mut Cursive) {
-    let mut file_selector: SelectView<OsString> = SelectView::new().autojump();
+    let mut file_selector: SelectView<ReaderState> = SelectView::new().autojump();
 
     s.with_user_data(|state: &mut XxvState| {
         let recent_files = state.recent_files();
         for recent_file in recent_files {
-            let path = recent_file.path();
-            file_selector.add_item(format!("{}", path.display()), path.into());
+            let path = recent_file.path().display();
+            let label = match recent_file.member() {
+                Some(member_name) => format!("{} \u{25B8} {}", path, member_name),
+                None if recent_file.is_decompressed() => format!("{} (decompressed)", path),
+                None => format!("{}", path),
+            };
+            file_selector.add_item(label, recent_file.clone());
         }
     })
     .unwrap();
@@ -44,38 +46,39 @@ pub fn switch_file_dialog(s: &mut Cursive) {
 
 fn do_switch_file(s: &mut Cursive) {
     let file_selector = s
-        .find_name::<SelectView<OsString>>(OBJ_SWITCHER)
+        .find_name::<SelectView<ReaderState>>(OBJ_SWITCHER)
         .unwrap();
     s.pop_layer();
     if let Some(rc_file) = file_selector.selection() {
-        let file_name = rc_file.as_ref();
-        let current_file = s
-            .call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.get_reader_state())
-            .unwrap();
-        if let Some(reader_result) = s.with_user_data(|state: &mut XxvState| {
-            let path = PathBuf::from(file_name);
-            let result = state.open_reader(path);
-            if result.is_ok() {
-                state.close_reader(current_file);
-            }
-            result
-        }) {
-            match reader_result {
-                Ok(reader) => s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
-                    view.switch_reader(reader);
-                }),
-                Err(error) => {
-                    s.show_error(error);
-                    None
-                }
-            };
+        confirm_unsaved_changes(s, move |s| switch_file(s, &rc_file));
+    }
+}
+
+fn switch_file(s: &mut Cursive, recent_file: &ReaderState) {
+    let current_file = s
+        .call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.get_reader_state())
+        .unwrap();
+    if let Some(reader_result) = s.with_user_data(|state: &mut XxvState| {
+        let result = state.open_recent_file(recent_file);
+        if result.is_ok() {
+            state.close_reader(current_file);
+        }
+        result
+    }) {
+        match reader_result {
+            Ok(reader) => {
+                s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.switch_reader(reader));
+                watch_input(s);
+                offer_decompression(s, recent_file.member().map(|m| m.to_string()));
+            },
+            Err(error) => s.show_error(error),
         }
     }
 }
 
 fn remove_selected_file(s: &mut Cursive) {
     let mut file_selector = s
-        .find_name::<SelectView<OsString>>(OBJ_SWITCHER)
+        .find_name::<SelectView<ReaderState>>(OBJ_SWITCHER)
         .unwrap();
     if let Some(id) = file_selector.selected_id() {
         file_selector.remove_item(id)(s);
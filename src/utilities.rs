@@ -14,20 +14,17 @@ pub const PKG_DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 pub const PKG_REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 
 pub fn parse_number(number_str: &str) -> Result<u64, ParseIntError> {
-    if number_str.starts_with("0x") {
-        u64::from_str_radix(&number_str[2..], 16)
+    if let Some(hex) = number_str.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
     } else if number_str.starts_with('0') {
         u64::from_str_radix(number_str, 8)
     } else {
-        u64::from_str_radix(number_str, 10)
+        number_str.parse()
     }
 }
 
 pub fn parse_number_or_zero(number_str: &str) -> u64 {
-    match parse_number(number_str) {
-        Ok(number) => number,
-        _ => 0,
-    }
+    parse_number(number_str).unwrap_or_default()
 }
 
 pub fn project_dirs() -> Option<ProjectDirs> {
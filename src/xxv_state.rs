@@ -2,7 +2,7 @@ use std::env;
 use std::ffi::OsStr;
 use std::fs;
 use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
 use std::path::{Path, PathBuf};
 
 use cursive::theme::{Palette, Theme};
@@ -13,18 +13,31 @@ use rmp_serde::Serializer;
 use serde::ser::Serialize;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::byte_reader::TilingByteReader;
+use crate::archive::Archive;
+use crate::byte_reader::{Snapshot, TilingByteReader};
+use crate::decompress::Compression;
 use crate::hex_reader::{HexReader, VisualMode};
 use crate::utilities;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReaderState {
     path: PathBuf,
     line_width: u64,
     group: u16,
     window_pos: (u64,u64),
     window_size: (u16,u16),
-    vis_mode: String
+    vis_mode: String,
+    #[serde(default)]
+    cursor: u64,
+    /// The name of the member, when the file is a member of the archive at the path.
+    #[serde(default)]
+    member: Option<String>,
+    /// Whether the decompressed contents of the file were shown, rather than its raw bytes.
+    #[serde(default)]
+    decompressed: bool,
+    /// Streams and process memory cannot be opened again, so they are not remembered as recent files.
+    #[serde(skip)]
+    transient: bool,
 }
 
 impl ReaderState {
@@ -36,17 +49,30 @@ impl ReaderState {
             window_pos: reader.window_pos,
             window_size: reader.window_size,
             vis_mode: reader.vis_mode.into(),
+            cursor: reader.cursor,
+            member: reader.member_name().map(|name| name.to_string()),
+            decompressed: reader.compression().is_some(),
+            transient: (reader.is_stream() && reader.compression().is_none() && reader.member_name().is_none())
+                || reader.is_process_memory(),
         }
     }
     
     pub fn path(&self) -> &Path {
         self.path.as_path()
     }
+    
+    pub fn member(&self) -> Option<&str> {
+        self.member.as_deref()
+    }
+    
+    pub fn is_decompressed(&self) -> bool {
+        self.decompressed
+    }
 }
 
 impl PartialEq for ReaderState {
     fn eq(&self, other: &Self) -> bool {
-        self.path.eq(&other.path)
+        self.path.eq(&other.path) && self.member.eq(&other.member) && self.decompressed == other.decompressed
     }
 }
 
@@ -71,12 +97,45 @@ impl From<String> for VisualMode {
     }
 }
 
+/// The number of recent queries that are remembered in the search history.
+const MAX_SEARCH_HISTORY: usize = 50;
+
+/// Which field of the search dialog a query was typed into.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum QueryMode {
+    #[default]
+    Text,
+    Hex,
+    Regex,
+    Number,
+}
+
+/// A query of the search history, with the options it was searched with.
+/// The number type, byte order and encoding are stored by their names.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub mode: QueryMode,
+    pub query: String,
+    #[serde(default)]
+    pub number_type: String,
+    #[serde(default)]
+    pub byte_order: String,
+    #[serde(default)]
+    pub tolerance: String,
+    #[serde(default)]
+    pub encoding: String,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct XxvState {
     theme: bool,
     current_dir: PathBuf,
     max_recent_files: usize,
     recent_files: Vec<ReaderState>,
+    #[serde(default)]
+    search_history: Vec<SearchQuery>,
 }
 
 impl XxvState {
@@ -106,7 +165,8 @@ impl XxvState {
             theme: true,
             current_dir,
             max_recent_files: 50,
-            recent_files: Vec::new()
+            recent_files: Vec::new(),
+            search_history: Vec::new(),
         }
     }
     
@@ -125,8 +185,53 @@ impl XxvState {
         }
     }
     
+    /// Open the file as it is, even if it is compressed.
     pub fn open_reader<P: AsRef<Path>>(&mut self, file_name: P) -> Result<HexReader> {
-        let b_reader = TilingByteReader::new(file_name)?;
+        let b_reader = TilingByteReader::open(file_name)?;
+        self.restore_reader_state(b_reader)
+    }
+    
+    /// Open the file decompressed, if it is compressed.
+    pub fn open_decompressed_reader<P: AsRef<Path>>(&mut self, file_name: P) -> Result<HexReader> {
+        let b_reader = match Compression::of_file(file_name.as_ref())? {
+            Some(compression) => TilingByteReader::decompress(file_name, compression)?,
+            None => TilingByteReader::open(file_name)?,
+        };
+        self.restore_reader_state(b_reader)
+    }
+    
+    /// Open a member of the archive, extracted. A compressed archive has to be decompressed in the viewer first.
+    pub fn open_member<P: AsRef<Path>>(&mut self, archive_path: P, member_name: &str) -> Result<HexReader> {
+        let archive_path = archive_path.as_ref().canonicalize()?;
+        if let Some(compression) = Compression::of_file(&archive_path)? {
+            let message = format!("{} is compressed with {}, and has to be decompressed first.", archive_path.display(), compression.name());
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        let mut snapshot = Snapshot::new(File::open(&archive_path)?)?;
+        let not_found = || {
+            let message = format!("{} is not in {}.", member_name, archive_path.display());
+            Error::new(ErrorKind::NotFound, message)
+        };
+        let member = Archive::read(&mut snapshot)?
+            .and_then(|archive| archive.member(member_name).cloned())
+            .ok_or_else(not_found)?;
+        let b_reader = TilingByteReader::extract(member.open(snapshot)?, &archive_path, member_name)?;
+        self.restore_reader_state(b_reader)
+    }
+    
+    /// Open a recent file the way it was shown: decompressed, extracted from an archive, or as it is.
+    /// The member of a compressed archive cannot be extracted until the archive is decompressed, so the archive is opened instead.
+    pub fn open_recent_file(&mut self, recent: &ReaderState) -> Result<HexReader> {
+        match &recent.member {
+            Some(_) if Compression::of_file(&recent.path)?.is_some() => self.open_reader(&recent.path),
+            Some(member_name) => self.open_member(&recent.path, member_name),
+            None if recent.decompressed => self.open_decompressed_reader(&recent.path),
+            None => self.open_reader(&recent.path),
+        }
+    }
+    
+    /// Read the bytes, and show them as they were viewed when they were last closed.
+    pub fn restore_reader_state(&mut self, b_reader: TilingByteReader) -> Result<HexReader> {
         match HexReader::new(b_reader) {
             Ok(mut reader) => {
                 let lookup_state = ReaderState::new(&reader);
@@ -136,8 +241,13 @@ impl XxvState {
                     reader.group = state.group;
                     reader.window_pos = state.window_pos;
                     reader.window_size = state.window_size;
+                    reader.cursor = state.cursor;
                     self.recent_files.remove(index);
                 };
+                // The file may have become shorter since it was last viewed. The length of a stream is known once all of it has arrived.
+                if !reader.is_stream() {
+                    reader.clamp_to_length();
+                }
                 Ok(reader)
             },
             err => err
@@ -145,6 +255,9 @@ impl XxvState {
     }
     
     pub fn close_reader(&mut self, reader: ReaderState) {
+        if reader.transient {
+            return;
+        }
         if let Some(index) = self.index_of(&reader) {
             self.recent_files.remove(index);
             self.recent_files.insert(0, reader);
@@ -157,12 +270,7 @@ impl XxvState {
     }
     
     fn index_of(&self, reader: &ReaderState) -> Option<usize> {
-        for i in 0..self.recent_files.len() {
-            if self.recent_files[i].eq(reader) {
-                return Some(i);
-            }
-        }
-        None
+        self.recent_files.iter().position(|file| file.eq(reader))
     }
     
     pub fn remove_recent_file(&mut self, index: usize) {
@@ -171,7 +279,7 @@ impl XxvState {
     
     pub fn recent_files(&mut self) -> &[ReaderState] {
         let mut outdated = Vec::new();
-        for (i, file) in (&self.recent_files).iter().enumerate() {
+        for (i, file) in self.recent_files.iter().enumerate() {
             if !file.path.exists() {
                 outdated.push(i);
             }
@@ -185,6 +293,18 @@ impl XxvState {
         &self.recent_files
     }
     
+    /// Remember a search query, as the most recent one.
+    pub fn add_search_query(&mut self, query: SearchQuery) {
+        self.search_history.retain(|q| *q != query);
+        self.search_history.insert(0, query);
+        self.search_history.truncate(MAX_SEARCH_HISTORY);
+    }
+    
+    /// The recent search queries, the most recent first.
+    pub fn search_history(&self) -> &[SearchQuery] {
+        &self.search_history
+    }
+    
     pub fn change_directory(&mut self, cd: &OsStr) {
         if cd == ".." {
             self.current_dir.pop();
@@ -239,3 +359,195 @@ impl XxvState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_state() -> XxvState {
+        XxvState {
+            theme: true,
+            current_dir: PathBuf::from("/"),
+            max_recent_files: 50,
+            recent_files: Vec::new(),
+            search_history: Vec::new(),
+        }
+    }
+
+    fn query(mode: QueryMode, query: &str) -> SearchQuery {
+        SearchQuery { mode, query: query.to_string(), ..SearchQuery::default() }
+    }
+
+    #[test]
+    fn search_history_keeps_recent_queries_first() {
+        let mut state = new_state();
+        state.add_search_query(query(QueryMode::Text, "abc"));
+        state.add_search_query(query(QueryMode::Hex, "61 62"));
+        state.add_search_query(query(QueryMode::Text, "abc"));
+        assert_eq!(state.search_history(), &[query(QueryMode::Text, "abc"), query(QueryMode::Hex, "61 62")]);
+        for i in 0..MAX_SEARCH_HISTORY {
+            state.add_search_query(query(QueryMode::Number, &i.to_string()));
+        }
+        assert_eq!(state.search_history().len(), MAX_SEARCH_HISTORY);
+        assert_eq!(state.search_history()[0], query(QueryMode::Number, &(MAX_SEARCH_HISTORY - 1).to_string()));
+    }
+
+    #[test]
+    fn search_history_is_stored_with_the_state() {
+        let mut state = new_state();
+        state.add_search_query(query(QueryMode::Regex, "PK\\x03\\x04"));
+        let bytes = rmp_serde::to_vec(&state).unwrap();
+        let loaded: XxvState = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(loaded.search_history(), state.search_history());
+
+        // State files from before there was a search history can still be loaded.
+        let old_state = (true, PathBuf::from("/"), 50usize, Vec::<ReaderState>::new());
+        let bytes = rmp_serde::to_vec(&old_state).unwrap();
+        let loaded: XxvState = rmp_serde::from_slice(&bytes).unwrap();
+        assert!(loaded.search_history().is_empty());
+    }
+    
+    #[test]
+    fn search_options_are_stored_with_the_query() {
+        let number = SearchQuery {
+            number_type: "f32".to_string(),
+            byte_order: "Big endian".to_string(),
+            tolerance: "0.01".to_string(),
+            ..query(QueryMode::Number, "1.5")
+        };
+        let text = SearchQuery { encoding: "UTF-16 LE".to_string(), ignore_case: true, ..query(QueryMode::Text, "abc") };
+        let mut state = new_state();
+        state.add_search_query(number);
+        state.add_search_query(text);
+        let bytes = rmp_serde::to_vec(&state).unwrap();
+        let loaded: XxvState = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(loaded.search_history(), state.search_history());
+        
+        // Queries from before the options were stored are loaded with empty options.
+        #[derive(Serialize)]
+        struct OldQuery {
+            mode: QueryMode,
+            query: String,
+        }
+        let old_query = OldQuery { mode: QueryMode::Hex, query: "41".to_string() };
+        let loaded: SearchQuery = rmp_serde::from_slice(&rmp_serde::to_vec(&old_query).unwrap()).unwrap();
+        assert_eq!(loaded, query(QueryMode::Hex, "41"));
+    }
+    
+    #[test]
+    fn compressed_files_are_opened_decompressed_or_raw() {
+        use std::io::Write;
+        
+        let mut tmpf = tempfile::Builder::new().suffix(".gz").tempfile().unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"decompressed").unwrap();
+        tmpf.write_all(&encoder.finish().unwrap()).unwrap();
+        let mut state = new_state();
+        
+        let reader = state.open_decompressed_reader(tmpf.path()).unwrap();
+        assert_eq!(reader.compression(), Some(Compression::Gzip));
+        assert_eq!(reader.get_path(), tmpf.path().canonicalize().unwrap());
+        let name = tmpf.path().file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(reader.file_name(), format!("{} \u{2192} {}", name, name.trim_end_matches(".gz")));
+        let mut reader_state = ReaderState::new(&reader);
+        reader_state.cursor = 5;
+        state.close_reader(reader_state);
+        assert_eq!(state.recent_files().len(), 1);
+        
+        // The raw bytes are a different recent file than the decompressed contents.
+        let raw_reader = state.open_reader(tmpf.path()).unwrap();
+        assert_eq!(raw_reader.compression(), None);
+        assert_eq!(raw_reader.file_name(), name);
+        assert_eq!(raw_reader.cursor, 0);
+        state.close_reader(ReaderState::new(&raw_reader));
+        assert_eq!(state.recent_files().len(), 2);
+        
+        let recent = state.recent_files()[1].clone();
+        assert!(recent.is_decompressed());
+        let reopened = state.open_recent_file(&recent).unwrap();
+        assert_eq!(reopened.compression(), Some(Compression::Gzip));
+        assert_eq!(reopened.cursor, 5);
+    }
+    
+    #[test]
+    fn positions_past_the_end_of_a_shorter_file_are_clamped() {
+        use std::io::Write;
+        
+        let mut tmpf = tempfile::NamedTempFile::new().unwrap();
+        tmpf.write_all(&[0; 1000]).unwrap();
+        let mut state = new_state();
+        let mut reader = state.open_reader(tmpf.path()).unwrap();
+        reader.cursor = 900;
+        reader.window_pos = (0, 50);
+        state.close_reader(ReaderState::new(&reader));
+        
+        tmpf.as_file().set_len(100).unwrap();
+        let reader = state.open_reader(tmpf.path()).unwrap();
+        assert_eq!(reader.cursor, 99);
+        assert_eq!(reader.window_pos, (0, 6));
+    }
+    
+    fn ar_archive() -> Vec<u8> {
+        let mut ar = b"!<arch>\n".to_vec();
+        ar.extend(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", "member.bin/", 0, 0, 0, 644, 6).as_bytes());
+        ar.extend(b"member");
+        ar
+    }
+    
+    #[test]
+    fn archive_members_are_remembered_as_recent_files() {
+        use std::io::Write;
+        
+        let mut tmpf = tempfile::Builder::new().suffix(".a").tempfile().unwrap();
+        tmpf.write_all(&ar_archive()).unwrap();
+        let mut state = new_state();
+        
+        let mut reader = state.open_member(tmpf.path(), "member.bin").unwrap();
+        assert_eq!(reader.member_name(), Some("member.bin"));
+        assert_eq!(reader.get_path(), tmpf.path().canonicalize().unwrap());
+        let progress = reader.input_progress().unwrap().clone();
+        while !progress.is_finished() {
+            std::thread::yield_now();
+        }
+        reader.update_length();
+        assert_eq!(reader.get_length(), 6);
+        reader.cursor = 3;
+        state.close_reader(ReaderState::new(&reader));
+        
+        // The archive itself is a different recent file than its member.
+        let archive_reader = state.open_reader(tmpf.path()).unwrap();
+        assert_eq!(archive_reader.cursor, 0);
+        state.close_reader(ReaderState::new(&archive_reader));
+        assert_eq!(state.recent_files().len(), 2);
+        
+        let recent = state.recent_files()[1].clone();
+        assert_eq!(recent.member(), Some("member.bin"));
+        let reopened = state.open_recent_file(&recent).unwrap();
+        assert_eq!(reopened.cursor, 3);
+        assert!(state.open_member(tmpf.path(), "missing.bin").is_err());
+    }
+    
+    #[test]
+    fn members_of_compressed_archives_are_not_extracted_without_decompressing() {
+        use std::io::Write;
+        
+        let mut tmpf = tempfile::Builder::new().suffix(".a.gz").tempfile().unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&ar_archive()).unwrap();
+        tmpf.write_all(&encoder.finish().unwrap()).unwrap();
+        let mut state = new_state();
+        assert_eq!(state.open_member(tmpf.path(), "member.bin").unwrap_err().kind(), ErrorKind::InvalidInput);
+        
+        // Reopening the member opens the raw archive, which is decompressed in the viewer first.
+        let archive_path = tmpf.path().canonicalize().unwrap();
+        let b_reader = TilingByteReader::extract(std::io::Cursor::new(b"member"), &archive_path, "member.bin").unwrap();
+        let member = state.restore_reader_state(b_reader).unwrap();
+        state.close_reader(ReaderState::new(&member));
+        let recent = state.recent_files()[0].clone();
+        assert_eq!(recent.member(), Some("member.bin"));
+        let reader = state.open_recent_file(&recent).unwrap();
+        assert_eq!(reader.member_name(), None);
+        assert_eq!(reader.compression(), None);
+        assert_eq!(reader.get_path(), archive_path);
+    }
+}
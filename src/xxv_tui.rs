@@ -1,25 +1,38 @@
-use std::io::Error;
+use std::env;
+use std::io::{Error, Result};
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
 
 use cursive::Cursive;
 use cursive::CursiveExt;
-use cursive::event::Key;
+use cursive::event::{Event, Key};
 use cursive::traits::{Resizable, Nameable};
-use cursive::views::{Dialog, LinearLayout, TextView};
+use cursive::views::{Dialog, LinearLayout, OnEventView, TextView};
 
+use crate::archive_dialog::{extract_member_named, open_archive_dialog};
+use crate::copy_dialog::open_copy_dialog;
+use crate::decompress::{Compression, MAX_DECOMPRESSED_LENGTH};
 use crate::search_dialog::search_dialog;
+use crate::results_dialog::open_results_dialog;
+use crate::mappings_dialog::open_mappings_dialog;
 use crate::goto_dialog::open_goto_dialog;
 use crate::help_text::show_help;
 use crate::hex_reader::HexReader;
 use crate::hex_view::HexView;
 use crate::open_file_dialog::open_file_dialog;
 use crate::panic_hook::archive_last_crash;
+use crate::replace_dialog::open_save_copy_dialog;
 use crate::set_width_dialog::open_set_width_dialog;
 use crate::status_bar::new_status_bar;
 use crate::switch_file_dialog::switch_file_dialog;
 use crate::utilities::{exit_reader_open_error, PKG_REPOSITORY};
 use crate::xxv_state::XxvState;
 
+/// How often the view is updated with more input, while reading from a stream.
+const INPUT_REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
 pub const OBJ_HEX_VIEW: &str = "hex_view";
 pub const OBJ_SWITCHER: &str = "file_switcher";
 pub const OBJ_CURRENT_DIR: &str = "current_dir";
@@ -32,6 +45,28 @@ pub const OBJ_GOTO_MUL1: &str = "goto_mul1";
 pub const OBJ_GOTO_MUL2: &str = "goto_mul2";
 pub const OBJ_FIND_ASCII: &str = "find_ascii";
 pub const OBJ_FIND_HEX: &str = "find_hex";
+pub const OBJ_FIND_IGNORE_CASE: &str = "find_ignore_case";
+pub const OBJ_FIND_ENCODING: &str = "find_encoding";
+pub const OBJ_FIND_REGEX: &str = "find_regex";
+pub const OBJ_FIND_NUMBER: &str = "find_number";
+pub const OBJ_FIND_NUMBER_TYPE: &str = "find_number_type";
+pub const OBJ_FIND_BYTE_ORDER: &str = "find_byte_order";
+pub const OBJ_FIND_TOLERANCE: &str = "find_tolerance";
+pub const OBJ_FIND_RANGE_START: &str = "find_range_start";
+pub const OBJ_FIND_RANGE_END: &str = "find_range_end";
+pub const OBJ_FIND_REPLACE: &str = "find_replace";
+pub const OBJ_REPLACE_STATUS: &str = "replace_status";
+pub const OBJ_SAVE_COPY_PATH: &str = "save_copy_path";
+pub const OBJ_RESULTS_FILTER: &str = "results_filter";
+pub const OBJ_RESULTS_ORDER: &str = "results_order";
+pub const OBJ_RESULTS_LIST: &str = "results_list";
+pub const OBJ_COPY_OFFSET: &str = "copy_offset";
+pub const OBJ_COPY_LENGTH: &str = "copy_length";
+pub const OBJ_COPY_FORMAT: &str = "copy_format";
+pub const OBJ_MAPPINGS_FILTER: &str = "mappings_filter";
+pub const OBJ_MAPPINGS_LIST: &str = "mappings_list";
+pub const OBJ_ARCHIVE_FILTER: &str = "archive_filter";
+pub const OBJ_ARCHIVE_LIST: &str = "archive_list";
 
 pub fn run_tui(reader: Option<HexReader>, mut state: XxvState) {
     let mut tui = Cursive::default();
@@ -47,24 +82,28 @@ pub fn run_tui(reader: Option<HexReader>, mut state: XxvState) {
     tui.add_global_callback('o', open_file_dialog);
     tui.add_global_callback('s', switch_file_dialog);
     tui.add_global_callback('/', search_dialog);
+    tui.add_global_callback('m', open_results_dialog);
+    tui.add_global_callback('c', open_copy_dialog);
+    tui.add_global_callback('M', open_mappings_dialog);
+    tui.add_global_callback('z', toggle_decompression);
+    tui.add_global_callback('a', open_archive_dialog);
+    tui.add_global_callback(Event::CtrlChar('s'), save_file);
 
-    let hex_view = match reader {
-        Some(reader) => HexView::new(reader),
+    let (hex_view, member) = match reader {
+        Some(reader) => (HexView::new(reader), None),
         None => {
-            let recent = state.recent_files();
-            let file_name = recent[0].path().to_path_buf();
-            match state.open_reader(&file_name) {
-                Ok(reader) => HexView::new(reader),
-                Err(e) => exit_reader_open_error(e, file_name.as_os_str()),
+            let recent = state.recent_files()[0].clone();
+            match state.open_recent_file(&recent) {
+                Ok(reader) => (HexView::new(reader), recent.member().map(|m| m.to_string())),
+                Err(e) => exit_reader_open_error(e, recent.path().as_os_str()),
             }
         }
-    }
-    .with_name(OBJ_HEX_VIEW);
+    };
+    let status_bar = new_status_bar(hex_view.status_content());
+    let hex_view = hex_view.with_name(OBJ_HEX_VIEW);
 
     tui.set_user_data(state);
 
-    let status_bar = new_status_bar();
-
     tui.screen_mut().add_transparent_layer(
         LinearLayout::vertical()
             .child(hex_view)
@@ -72,14 +111,20 @@ pub fn run_tui(reader: Option<HexReader>, mut state: XxvState) {
             .full_screen(),
     );
 
+    offer_decompression(&mut tui, member);
     if let Some(archived_crash_log) = archive_last_crash() {
         show_crash_dialog(&mut tui, archived_crash_log);
     }
+    watch_input(&mut tui);
 
     tui.run();
 }
 
 fn quit(s: &mut Cursive) {
+    confirm_unsaved_changes(s, do_quit);
+}
+
+fn do_quit(s: &mut Cursive) {
     let reader_state = s
         .call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.get_reader_state())
         .unwrap();
@@ -90,6 +135,191 @@ fn quit(s: &mut Cursive) {
     s.quit()
 }
 
+/// Keep showing more of the input as it arrives, if the current file is a stream, until all of it has arrived.
+pub fn watch_input(s: &mut Cursive) {
+    let progress = match s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.input_progress()).flatten() {
+        Some(progress) => progress,
+        None => return,
+    };
+    let sink = s.cb_sink().clone();
+    thread::spawn(move || loop {
+        let finished = progress.is_finished();
+        thread::sleep(INPUT_REFRESH_INTERVAL);
+        let update = Box::new(|s: &mut Cursive| {
+            s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.update_input());
+        });
+        if sink.send(update).is_err() || finished {
+            break;
+        }
+    });
+}
+
+/// Switch between the decompressed contents of a compressed file, and its raw compressed bytes.
+fn toggle_decompression(s: &mut Cursive) {
+    let (path, compression, can_save) = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
+        (view.file_path(), view.compression(), view.can_save())
+    }).unwrap();
+    let raw_compression = if can_save { Compression::of_file(&path).unwrap_or(None) } else { None };
+    let message = match (compression, raw_compression) {
+        (Some(compression), _) => format!("Showing the raw {} bytes", compression.name()),
+        (None, Some(compression)) => format!("Showing the decompressed {} contents", compression.name()),
+        (None, None) => {
+            s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.show_message("The file is not compressed".to_string()));
+            return;
+        },
+    };
+    let decompress = compression.is_none();
+    confirm_unsaved_changes(s, move |s| {
+        replace_reader(s, message.clone(), |state| if decompress { state.open_decompressed_reader(&path) } else { state.open_reader(&path) });
+    });
+}
+
+/// Keep checking whether all of the input has arrived, and then run the action.
+/// The action is not run if reading the input failed, or if another file is shown by then.
+fn when_input_read<F>(s: &mut Cursive, action: F)
+where
+    F: FnOnce(&mut Cursive) + Send + 'static,
+{
+    let progress = match s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.input_progress()).flatten() {
+        Some(progress) => progress,
+        None => return,
+    };
+    let sink = s.cb_sink().clone();
+    thread::spawn(move || {
+        while !progress.is_finished() {
+            thread::sleep(INPUT_REFRESH_INTERVAL);
+        }
+        let _ = sink.send(Box::new(move |s: &mut Cursive| {
+            let current = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
+                view.update_input();
+                view.input_progress()
+            }).flatten();
+            if current.is_some_and(|current| current.is_same_input(&progress)) && progress.error().is_none() {
+                action(s);
+            }
+        }));
+    });
+}
+
+/// Ask whether to show the decompressed contents of the file that was just opened, if it is compressed.
+/// Decompressing writes the contents to a temporary file, so it is not done without asking.
+/// The member is extracted from the archive once it has been decompressed, when reopening a member of a compressed archive.
+pub fn offer_decompression(s: &mut Cursive, member: Option<String>) {
+    let (path, name, compression, can_save) = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
+        (view.file_path(), view.file_name().to_string(), view.compression(), view.can_save())
+    }).unwrap();
+    if compression.is_some() || !can_save {
+        return;
+    }
+    let compression = match Compression::of_file(&path) {
+        Ok(Some(compression)) => compression,
+        _ => return,
+    };
+    let question = match &member {
+        Some(member_name) => format!(
+            "{} is a member of {}, which is compressed with {}. Decompress the archive, and then extract {}?",
+            member_name, name, compression.name(), member_name),
+        None => format!("{} is compressed with {}. Decompress it?", name, compression.name()),
+    };
+    let text = format!(
+        "{}\n\n\
+        The decompressed contents are written to a temporary file in {}, up to {} GiB. \
+        Press z to switch between the decompressed and the raw bytes later.",
+        question, env::temp_dir().display(), MAX_DECOMPRESSED_LENGTH >> 30);
+    let message = format!("Showing the decompressed {} contents", compression.name());
+    let dialog = Dialog::text(text)
+        .title("Compressed file")
+        .button("Decompress", move |s| {
+            s.pop_layer();
+            replace_reader(s, message.clone(), |state| state.open_decompressed_reader(&path));
+            if let Some(member_name) = member.clone() {
+                when_input_read(s, move |s| extract_member_named(s, &member_name));
+            }
+        })
+        .dismiss_button("Show raw bytes");
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+    s.add_layer(esc_view);
+}
+
+/// Show the newly opened reader instead of the current one, which is remembered as a recent file.
+pub fn replace_reader<F>(s: &mut Cursive, message: String, open: F)
+where
+    F: FnOnce(&mut XxvState) -> Result<HexReader>,
+{
+    let current_file = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.get_reader_state()).unwrap();
+    let result = s.with_user_data(|state: &mut XxvState| {
+        let result = open(state);
+        if result.is_ok() {
+            state.close_reader(current_file);
+        }
+        result
+    }).unwrap();
+    match result {
+        Ok(reader) => {
+            s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| {
+                view.switch_reader(reader);
+                view.show_message(message);
+            });
+            watch_input(s);
+        },
+        Err(error) => s.show_error(error),
+    }
+}
+
+fn save_file(s: &mut Cursive) {
+    let can_save = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.can_save()).unwrap();
+    if !can_save {
+        // Streams and process memory have no file to save to.
+        open_save_copy_dialog(s);
+        return;
+    }
+    let result = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.save()).unwrap();
+    if let Err(error) = result {
+        s.show_error(error);
+    }
+}
+
+/// Run the given action right away, if the current file has no unsaved changes.
+/// Otherwise, ask the user if the changes should be saved or discarded first.
+pub fn confirm_unsaved_changes<F>(s: &mut Cursive, action: F)
+where
+    F: Fn(&mut Cursive) + 'static,
+{
+    let unsaved = s
+        .call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.has_unsaved_changes())
+        .unwrap();
+    if !unsaved {
+        action(s);
+        return;
+    }
+
+    let discard_action = Rc::new(action);
+    let save_action = discard_action.clone();
+    let dialog = Dialog::text("The file has unsaved changes.")
+        .title("Unsaved changes")
+        .button("Save", move |s| {
+            s.pop_layer();
+            let result = s.call_on_name(OBJ_HEX_VIEW, |view: &mut HexView| view.save()).unwrap();
+            match result {
+                Ok(()) => save_action(s),
+                Err(error) => s.show_error(error),
+            }
+        })
+        .button("Discard", move |s| {
+            s.pop_layer();
+            discard_action(s);
+        })
+        .dismiss_button("Cancel");
+    let esc_view = OnEventView::new(dialog)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+    s.add_layer(esc_view);
+}
+
 fn change_theme(s: &mut Cursive) {
     let new_theme = s.with_user_data(|state: &mut XxvState| {
         state.toggle_theme();